@@ -1,8 +1,71 @@
-pub const TRANSFER_IXS_CHUNK_SIZE: usize = 18;
+use solana_program::{pubkey, pubkey::Pubkey};
+
+// rough upper bounds of compute units per ix, used to keep packed txs under the compute unit limit
+pub const TRANSFER_CU_ESTIMATE: u32 = 15_000;
+pub const TRANSFER_HOOK_CU_ESTIMATE: u32 = 60_000;
+pub const CREATE_ATA_CU_ESTIMATE: u32 = 40_000;
+pub const MEMO_CU_ESTIMATE: u32 = 10_000;
 pub const ATA_GET_MULT_ACC_CHUNK_SIZE: usize = 100;
+// parallel getTokenAccountsByOwner requests when discovering token accounts of wallets without ata
+pub const TOKEN_ACCOUNT_DISCOVERY_CONCURRENCY: usize = 8;
+// addresses per extend lookup table ix, keeps the tx under the packet size
+pub const LOOKUP_TABLE_EXTEND_CHUNK_SIZE: usize = 20;
+pub const LOOKUP_TABLE_POLL_INTERVAL_MILLIS: u64 = 400;
+
+pub const DEFAULT_NONCE_POOL_SIZE: usize = 8;
+
+pub const DEFAULT_SEND_CONCURRENCY: usize = 8;
+pub const DEFAULT_BLOCKHASH_REFRESH_INTERVAL_SECS: u64 = 10;
+pub const SEND_MAX_RETRY: u32 = 3;
+pub const SEND_BACKOFF_BASE_MILLIS: u64 = 250;
+pub const SEND_MAX_BACKOFF_MILLIS: u64 = 8_000;
+pub const NONCE_POLL_INTERVAL_MILLIS: u64 = 400;
+// how long to wait for a nonce account of the pool to free up (~60s)
+pub const NONCE_MAX_POLLS: usize = 150;
 
 pub const CHECK_MAX_RETRY: usize = 4;
 
+// memo added to transfers to token accounts that require incoming transfer memos
+pub const AIRDROP_MEMO: &str = "airdrop";
+
+pub const METAPLEX_TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// token account layout
+pub const MINT_OFFSET: usize = 0;
+pub const OWNER_OFFSET: usize = 32;
+
+// token-2022 extension layout, the extension types below are unknown to the bundled spl-token-2022
+pub const TLV_HEADER_LEN: usize = 4;
+pub const CONFIDENTIAL_MINT_BURN_EXTENSION_TYPE: u16 = 24;
+pub const SCALED_UI_AMOUNT_EXTENSION_TYPE: u16 = 25;
+pub const PAUSABLE_EXTENSION_TYPE: u16 = 26;
+pub const PAUSABLE_ACCOUNT_EXTENSION_TYPE: u16 = 27;
+// PausableConfig: authority (32 bytes) then paused (bool)
+pub const PAUSABLE_CONFIG_PAUSED_OFFSET: usize = 32;
+
+// base fee, every airdrop tx is only signed by the payer
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
 pub const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 1_000_000;
 pub const DEFAULT_COMPUTE_UNIT_PRICE: u64 = 1;
+pub const DEFAULT_COMPUTE_UNIT_MARGIN_PERCENT: u32 = 10;
+pub const DEFAULT_PRIORITY_FEE_PERCENTILE: u8 = 75;
+pub const DEFAULT_MAX_COMPUTE_UNIT_PRICE: u64 = 1_000_000;
+pub const DEFAULT_PRIORITY_FEE_ESCALATION_PERCENT: u32 = 50;
+// getRecentPrioritizationFees accepts at most 128 accounts
+pub const PRIORITIZATION_FEE_MAX_ACCOUNTS: usize = 128;
 pub const DEFAULT_SNAPSHOT_MINIMUM_BALANCE_ATOMIC: u64 = 1;
+
+// multipliers are applied as fixed point with this precision to keep the pro rata math in integers
+pub const MULTIPLIER_PRECISION: u128 = 1_000_000;
+
+pub const RAFFLE_SLOT_POLL_INTERVAL_SECS: u64 = 5;
+
+pub const DEFAULT_PROJECT_CONFIG_PATH: &str = "airdropper.json";
+
+// used to detect which cluster an rpc endpoint serves
+pub const MAINNET_BETA_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+pub const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+pub const TESTNET_GENESIS_HASH: &str = "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY";
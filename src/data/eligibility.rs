@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, path::Path};
+
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::amount_to_ui_amount_string_trimmed;
+
+use crate::{errors::Result, utils::to_hex};
+
+use super::{Status, WalletListEntry};
+
+/// Allocation of a single wallet, as served to "am I eligible" frontends
+// NOTE: atomic amounts are strings since they might not fit into a js number
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Eligibility {
+    pub wallet_pubkey: String,
+    pub amount_atomic: String,
+    pub ui_amount: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disqualification_reason: Option<String>,
+}
+
+impl Eligibility {
+    pub fn from_entry(entry: &WalletListEntry, token_decimals: u8) -> Self {
+        Self {
+            wallet_pubkey: entry.wallet_pubkey.to_string(),
+            amount_atomic: entry.amount_to_airdrop.to_string(),
+            ui_amount: amount_to_ui_amount_string_trimmed(entry.amount_to_airdrop, token_decimals),
+            status: entry.status.to_string(),
+            signature: entry.status.signature().map(|sig| sig.to_string()),
+            disqualification_reason: match entry.status {
+                Status::Disqualified(reason) => Some(reason.to_string()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Written to index.json of the export directory
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EligibilityIndex {
+    pub mint: String,
+    pub decimals: u8,
+    pub wallet_count: usize,
+    pub total_amount_atomic: String,
+    pub total_ui_amount: String,
+    /// 0 if exported as one file per wallet (<wallet_pubkey>.json),
+    /// otherwise the length of the prefix each shard (<prefix>.json) is keyed by,
+    /// taken from the lowercase hex encoding of the 32 wallet pubkey bytes
+    pub shard_prefix_length: usize,
+}
+
+/// first prefix_length (<= 64) hex characters of the wallet pubkey bytes
+fn shard_key(wallet_pubkey: &Pubkey, prefix_length: usize) -> String {
+    let mut key = to_hex(&wallet_pubkey.to_bytes());
+    key.truncate(prefix_length);
+    key
+}
+
+pub fn export_eligibility(
+    dir: &Path,
+    token_mint_pubkey: &Pubkey,
+    token_decimals: u8,
+    entries: &[WalletListEntry],
+    shard_prefix_length: usize,
+) -> Result<EligibilityIndex> {
+    std::fs::create_dir_all(dir)?;
+
+    let eligibilities = entries
+        .iter()
+        .map(|entry| Eligibility::from_entry(entry, token_decimals));
+    if shard_prefix_length == 0 {
+        for eligibility in eligibilities {
+            std::fs::write(
+                dir.join(format!("{}.json", eligibility.wallet_pubkey)),
+                serde_json::to_string(&eligibility)?,
+            )?;
+        }
+    } else {
+        // NOTE: keyed by hex instead of base58 prefixes, which differ only in case (e.g. Ab and AB)
+        //  and would overwrite each other on case-insensitive filesystems
+        let mut shards: BTreeMap<String, BTreeMap<String, Eligibility>> = BTreeMap::new();
+        for (entry, eligibility) in entries.iter().zip(eligibilities) {
+            let prefix = shard_key(&entry.wallet_pubkey, shard_prefix_length);
+            shards
+                .entry(prefix)
+                .or_default()
+                .insert(eligibility.wallet_pubkey.clone(), eligibility);
+        }
+        log::info!("Writing {} shards ...", shards.len());
+        for (prefix, shard) in shards {
+            std::fs::write(
+                dir.join(format!("{prefix}.json")),
+                serde_json::to_string(&shard)?,
+            )?;
+        }
+    }
+
+    let total_amount: u64 = entries.iter().map(|entry| entry.amount_to_airdrop).sum();
+    let index = EligibilityIndex {
+        mint: token_mint_pubkey.to_string(),
+        decimals: token_decimals,
+        wallet_count: entries.len(),
+        total_amount_atomic: total_amount.to_string(),
+        total_ui_amount: amount_to_ui_amount_string_trimmed(total_amount, token_decimals),
+        shard_prefix_length,
+    };
+    std::fs::write(
+        dir.join("index.json"),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+    Ok(index)
+}
@@ -0,0 +1,44 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{errors::Result, utils::add_to_filename};
+
+/// Lookup tables created by send, kept until they are deactivated and closed (reclaiming their rent)
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct LookupTableList {
+    pub lookup_tables: Vec<String>,
+}
+
+impl LookupTableList {
+    // e.g. list.csv -> list.lookup_tables.json
+    pub fn path_from_wallet_list_path(wallet_list_path: &Path) -> PathBuf {
+        add_to_filename(wallet_list_path, "lookup_tables").with_extension("json")
+    }
+
+    pub fn parse_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing lookup table list from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let list = serde_json::from_str(&data)?;
+        log::info!("Finished parsing lookup table list");
+        Ok(list)
+    }
+
+    pub fn save_to_path(&self, path: &PathBuf) -> Result<()> {
+        log::info!("Saving lookup table list to {path:?} ...");
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::info!("Finished saving lookup table list");
+        Ok(())
+    }
+
+    pub fn pubkeys(&self) -> Result<Vec<Pubkey>> {
+        Ok(self
+            .lookup_tables
+            .iter()
+            .map(|pubkey| Pubkey::from_str(pubkey))
+            .collect::<std::result::Result<_, _>>()?)
+    }
+}
@@ -0,0 +1,225 @@
+use std::{path::PathBuf, str::FromStr};
+
+use solana_sdk::{
+    hash::{hashv, Hash},
+    pubkey::Pubkey,
+};
+
+use crate::errors::{Error, Result};
+
+// NOTE: leaf and intermediate node layout follows the widely used (jito) merkle-distributor:
+//  - node = sha256(claimant || amount_unlocked (u64 le) || amount_locked (u64 le))
+//  - leaf = sha256(LEAF_PREFIX || node)
+//  - intermediate = sha256(INTERMEDIATE_PREFIX || min(a, b) || max(a, b))
+//  - odd node at the end of a level is paired with itself
+const LEAF_PREFIX: &[u8] = &[0];
+const INTERMEDIATE_PREFIX: &[u8] = &[1];
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MerkleTreeNode {
+    pub claimant: String,
+    pub amount_unlocked: u64,
+    pub amount_locked: u64,
+    pub proof: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MerkleDistributorTree {
+    pub merkle_root: String,
+    pub max_num_nodes: u64,
+    pub max_total_claim: u64,
+    pub tree_nodes: Vec<MerkleTreeNode>,
+}
+
+impl MerkleDistributorTree {
+    /// Builds the tree with given (claimant, amount_unlocked) where nothing is locked
+    pub fn new(claims: &[(Pubkey, u64)]) -> Self {
+        let leaves: Vec<Hash> = claims
+            .iter()
+            .map(|(claimant, amount)| hash_leaf(claimant, *amount, 0))
+            .collect();
+        let levels = build_levels(leaves);
+        let merkle_root = levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_default();
+        let tree_nodes = claims
+            .iter()
+            .enumerate()
+            .map(|(idx, (claimant, amount))| MerkleTreeNode {
+                claimant: claimant.to_string(),
+                amount_unlocked: *amount,
+                amount_locked: 0,
+                proof: get_proof(&levels, idx)
+                    .iter()
+                    .map(|hash| hash.to_string())
+                    .collect(),
+            })
+            .collect();
+        Self {
+            merkle_root: merkle_root.to_string(),
+            max_num_nodes: claims.len() as u64,
+            max_total_claim: claims.iter().map(|(_, amount)| amount).sum(),
+            tree_nodes,
+        }
+    }
+
+    pub fn find_node(&self, claimant: &Pubkey) -> Option<&MerkleTreeNode> {
+        let claimant = claimant.to_string();
+        self.tree_nodes
+            .iter()
+            .find(|node| node.claimant == claimant)
+    }
+
+    pub fn parse_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing merkle tree from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let tree = serde_json::from_str(&data)?;
+        log::info!("Finished parsing merkle tree");
+        Ok(tree)
+    }
+
+    pub fn save_to_path(&self, path: &PathBuf) -> Result<()> {
+        log::info!("Saving merkle tree to {path:?} ...");
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::info!("Finished saving merkle tree");
+        Ok(())
+    }
+}
+
+pub fn hash_leaf(claimant: &Pubkey, amount_unlocked: u64, amount_locked: u64) -> Hash {
+    let node = hashv(&[
+        &claimant.to_bytes(),
+        &amount_unlocked.to_le_bytes(),
+        &amount_locked.to_le_bytes(),
+    ]);
+    hashv(&[LEAF_PREFIX, node.as_ref()])
+}
+
+fn hash_intermediate(a: &Hash, b: &Hash) -> Hash {
+    if a <= b {
+        hashv(&[INTERMEDIATE_PREFIX, a.as_ref(), b.as_ref()])
+    } else {
+        hashv(&[INTERMEDIATE_PREFIX, b.as_ref(), a.as_ref()])
+    }
+}
+
+// returns every level of the tree, from leaves to root
+fn build_levels(leaves: Vec<Hash>) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaves];
+    // UNWRAP-SAFETY: levels is never empty
+    while levels.last().unwrap().len() > 1 {
+        let next_level = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| hash_intermediate(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        levels.push(next_level);
+    }
+    levels
+}
+
+fn get_proof(levels: &[Vec<Hash>], mut idx: usize) -> Vec<Hash> {
+    let mut proof = Vec::new();
+    for level in levels.iter().take(levels.len().saturating_sub(1)) {
+        let sibling_idx = if idx % 2 == 1 {
+            idx - 1
+        } else if idx + 1 < level.len() {
+            idx + 1
+        } else {
+            idx
+        };
+        proof.push(level[sibling_idx]);
+        idx /= 2;
+    }
+    proof
+}
+
+pub fn verify_proof(root: &Hash, leaf: Hash, proof: &[String]) -> Result<bool> {
+    let computed = proof.iter().try_fold(leaf, |computed, sibling| {
+        Ok::<_, Error>(hash_intermediate(&computed, &Hash::from_str(sibling)?))
+    })?;
+    Ok(computed == *root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NOTE: fixed roots and proofs were computed independently (outside this crate) from the
+    //  merkle-distributor layout described at the top of this file
+    fn claims(n: u8) -> Vec<(Pubkey, u64)> {
+        (1..=n)
+            .map(|i| (Pubkey::new_from_array([i; 32]), i as u64 * 100))
+            .collect()
+    }
+
+    fn assert_every_proof_verifies(tree: &MerkleDistributorTree) {
+        let root = Hash::from_str(&tree.merkle_root).unwrap();
+        for node in tree.tree_nodes.iter() {
+            let claimant = Pubkey::from_str(&node.claimant).unwrap();
+            let leaf = hash_leaf(&claimant, node.amount_unlocked, node.amount_locked);
+            assert!(verify_proof(&root, leaf, &node.proof).unwrap());
+            let wrong_leaf = hash_leaf(&claimant, node.amount_unlocked + 1, node.amount_locked);
+            assert!(!verify_proof(&root, wrong_leaf, &node.proof).unwrap());
+        }
+    }
+
+    #[test]
+    fn matches_fixed_tree() {
+        let tree = MerkleDistributorTree::new(&claims(4));
+        assert_eq!(
+            tree.merkle_root,
+            "EGsrKzshY2zgFBgfrzT3rXaVQCB5Kw9GQBwEBQWeNY7e"
+        );
+        assert_eq!(tree.max_num_nodes, 4);
+        assert_eq!(tree.max_total_claim, 1_000);
+        let node = tree
+            .find_node(&Pubkey::from_str("GgBaCs3NCBuZN12kCJgAW63ydqohFkHEdfdEXBPzLHq").unwrap())
+            .unwrap();
+        assert_eq!(
+            node.proof,
+            vec![
+                "2bZxkotx37cByDy2bBss8FiEYQgLFu64EGikSVtUkdYv",
+                "HsEKBQEZ6wu8CrGFZAo3VjXEX9LdMHN23mQfxnqbAUnc",
+            ]
+        );
+        assert_every_proof_verifies(&tree);
+    }
+
+    #[test]
+    fn matches_fixed_tree_with_odd_number_of_leaves() {
+        let tree = MerkleDistributorTree::new(&claims(3));
+        assert_eq!(
+            tree.merkle_root,
+            "3f75ePoQ5HkfHvkorfnvRbtce5hjdGELFhPdXRuSVfzJ"
+        );
+        // the last leaf is paired with itself
+        let node = tree
+            .find_node(&Pubkey::from_str("CktRuQ2mttgRGkXJtyksdKHjUdc2C4TgDzyB98oEzy8").unwrap())
+            .unwrap();
+        assert_eq!(
+            node.proof,
+            vec![
+                "2bZxkotx37cByDy2bBss8FiEYQgLFu64EGikSVtUkdYv",
+                "HsEKBQEZ6wu8CrGFZAo3VjXEX9LdMHN23mQfxnqbAUnc",
+            ]
+        );
+        assert_every_proof_verifies(&tree);
+    }
+
+    #[test]
+    fn single_leaf_is_root() {
+        let claims = claims(1);
+        let tree = MerkleDistributorTree::new(&claims);
+        let (claimant, amount) = claims[0];
+        assert_eq!(
+            tree.merkle_root,
+            hash_leaf(&claimant, amount, 0).to_string()
+        );
+        assert!(tree.tree_nodes[0].proof.is_empty());
+        assert_every_proof_verifies(&tree);
+    }
+}
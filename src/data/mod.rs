@@ -2,9 +2,29 @@ use std::path::PathBuf;
 
 use crate::errors::Result;
 
+pub use eligibility::*;
+pub use lookup_table_list::*;
+pub use merkle_tree::*;
+pub use multiplier_list::*;
+pub use nonce_account_list::*;
+pub use project_config::*;
+pub use qualification_rules::*;
+pub use raffle::*;
+pub use send_journal::*;
+pub use sent_tx_list::*;
 pub use snapshot::*;
 pub use wallet_list::*;
 
+mod eligibility;
+mod lookup_table_list;
+mod merkle_tree;
+mod multiplier_list;
+mod nonce_account_list;
+mod project_config;
+mod qualification_rules;
+mod raffle;
+mod send_journal;
+mod sent_tx_list;
 mod snapshot;
 mod wallet_list;
 
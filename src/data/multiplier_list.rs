@@ -0,0 +1,125 @@
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    consts::MULTIPLIER_PRECISION,
+    errors::{Error, Result},
+};
+
+use super::{CsvEntrySer, CsvListSerde};
+
+/// Per-wallet multipliers applied to snapshot balances when generating a wallet list.
+/// Wallets not in the list get a multiplier of 1.
+#[derive(Debug, Default)]
+pub struct MultiplierList(pub Vec<MultiplierListEntry>);
+
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct MultiplierListEntryRaw {
+    pub wallet_pubkey: String,
+    pub multiplier: f64,
+}
+
+#[derive(Debug)]
+pub struct MultiplierListEntry {
+    pub wallet_pubkey: Pubkey,
+    pub multiplier: f64,
+}
+
+impl CsvEntrySer for MultiplierListEntry {
+    fn to_record(&self) -> Vec<String> {
+        vec![self.wallet_pubkey.to_string(), self.multiplier.to_string()]
+    }
+}
+
+impl TryFrom<MultiplierListEntryRaw> for MultiplierListEntry {
+    type Error = Error;
+
+    fn try_from(
+        MultiplierListEntryRaw {
+            wallet_pubkey,
+            multiplier,
+        }: MultiplierListEntryRaw,
+    ) -> std::prelude::v1::Result<Self, Self::Error> {
+        // NOTE: would otherwise be silently cast to 0 when converted to fixed point
+        if !multiplier.is_finite() || multiplier < 0.0 {
+            return Err(Error::AllocationError(format!(
+                "Invalid multiplier {multiplier} for {wallet_pubkey}"
+            )));
+        }
+        if multiplier > 0.0 && (multiplier * MULTIPLIER_PRECISION as f64).round() == 0.0 {
+            return Err(Error::AllocationError(format!(
+                "Multiplier {multiplier} for {wallet_pubkey} rounds to 0 at a precision of 1/{MULTIPLIER_PRECISION}, use 0 to exclude the wallet"
+            )));
+        }
+        Ok(MultiplierListEntry {
+            wallet_pubkey: Pubkey::from_str(&wallet_pubkey)?,
+            multiplier,
+        })
+    }
+}
+
+impl CsvListSerde for MultiplierList {
+    fn parse_list_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing multiplier list from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b',')
+            .has_headers(false)
+            .from_reader(data.as_bytes());
+        let list = rdr
+            .deserialize()
+            .collect::<std::result::Result<Vec<MultiplierListEntryRaw>, _>>()?;
+        let list = list
+            .into_iter()
+            .map(MultiplierListEntry::try_from)
+            .collect::<std::result::Result<Vec<MultiplierListEntry>, _>>()?;
+        log::info!("Finished parsing multiplier list");
+        Ok(Self(list))
+    }
+
+    fn save_to_path(&mut self, path: &PathBuf) -> Result<()> {
+        log::info!("Saving multiplier list to {path:?} ...");
+        let mut wtr = csv::Writer::from_path(path)?;
+        self.0.sort_by(|a, b| a.wallet_pubkey.cmp(&b.wallet_pubkey));
+        for entry in self.0.iter() {
+            wtr.write_record(entry.to_record())?;
+        }
+        wtr.flush()?;
+        log::info!("Finished saving multiplier list");
+        Ok(())
+    }
+}
+
+impl MultiplierList {
+    pub fn to_map(&self) -> HashMap<Pubkey, f64> {
+        self.0
+            .iter()
+            .map(|entry| (entry.wallet_pubkey, entry.multiplier))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(multiplier: f64) -> Result<MultiplierListEntry> {
+        MultiplierListEntry::try_from(MultiplierListEntryRaw {
+            wallet_pubkey: Pubkey::new_unique().to_string(),
+            multiplier,
+        })
+    }
+
+    #[test]
+    fn rejects_multipliers_that_cannot_be_weighted() {
+        assert!(parse(0.0).is_ok());
+        assert!(parse(1.0 / MULTIPLIER_PRECISION as f64).is_ok());
+        assert!(matches!(
+            parse(0.4 / MULTIPLIER_PRECISION as f64),
+            Err(Error::AllocationError(_))
+        ));
+        assert!(matches!(parse(-1.0), Err(Error::AllocationError(_))));
+        assert!(matches!(parse(f64::NAN), Err(Error::AllocationError(_))));
+    }
+}
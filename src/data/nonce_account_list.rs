@@ -0,0 +1,82 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+
+use crate::{errors::Result, utils::add_to_filename};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NonceAccountEntry {
+    pub pubkey: String,
+    /// durable nonce the last tx was built with (None if never used).
+    /// the account is in use until its nonce advances past it (i.e. the tx landed)
+    #[serde(default)]
+    pub last_used_nonce: Option<String>,
+}
+
+/// Nonce accounts (with the payer as authority) used by send in durable nonce mode,
+/// kept across runs so that the pool is reused
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct NonceAccountList {
+    pub nonce_accounts: Vec<NonceAccountEntry>,
+}
+
+impl NonceAccountList {
+    // e.g. list.csv -> list.nonce_accounts.json
+    pub fn path_from_wallet_list_path(wallet_list_path: &Path) -> PathBuf {
+        add_to_filename(wallet_list_path, "nonce_accounts").with_extension("json")
+    }
+
+    pub fn parse_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing nonce account list from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let list = serde_json::from_str(&data)?;
+        log::info!("Finished parsing nonce account list");
+        Ok(list)
+    }
+
+    pub fn save_to_path(&self, path: &PathBuf) -> Result<()> {
+        log::info!("Saving nonce account list to {path:?} ...");
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::info!("Finished saving nonce account list");
+        Ok(())
+    }
+
+    pub fn add(&mut self, pubkeys: &[Pubkey]) {
+        self.nonce_accounts
+            .extend(pubkeys.iter().map(|pubkey| NonceAccountEntry {
+                pubkey: pubkey.to_string(),
+                last_used_nonce: None,
+            }));
+    }
+
+    /// (nonce account, last used nonce) of every account in the pool
+    pub fn entries(&self) -> Result<Vec<(Pubkey, Option<Hash>)>> {
+        self.nonce_accounts
+            .iter()
+            .map(|entry| {
+                Ok((
+                    Pubkey::from_str(&entry.pubkey)?,
+                    entry
+                        .last_used_nonce
+                        .as_deref()
+                        .map(Hash::from_str)
+                        .transpose()?,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn set_last_used_nonce(&mut self, nonce_pubkey: &Pubkey, nonce: &Hash) {
+        let nonce_pubkey = nonce_pubkey.to_string();
+        if let Some(entry) = self
+            .nonce_accounts
+            .iter_mut()
+            .find(|entry| entry.pubkey == nonce_pubkey)
+        {
+            entry.last_used_nonce = Some(nonce.to_string());
+        }
+    }
+}
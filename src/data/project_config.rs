@@ -0,0 +1,99 @@
+use std::{collections::HashMap, fmt::Display, path::PathBuf};
+
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    hash::Hash,
+};
+
+use crate::{
+    consts::{DEVNET_GENESIS_HASH, MAINNET_BETA_GENESIS_HASH, TESTNET_GENESIS_HASH},
+    errors::{Error, Result},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cluster {
+    MainnetBeta,
+    Devnet,
+    Testnet,
+    // e.g. local test validator
+    Unknown,
+}
+
+impl Cluster {
+    pub fn from_genesis_hash(genesis_hash: &Hash) -> Self {
+        match genesis_hash.to_string().as_str() {
+            MAINNET_BETA_GENESIS_HASH => Self::MainnetBeta,
+            DEVNET_GENESIS_HASH => Self::Devnet,
+            TESTNET_GENESIS_HASH => Self::Testnet,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl Display for Cluster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MainnetBeta => write!(f, "mainnet-beta"),
+            Self::Devnet => write!(f, "devnet"),
+            Self::Testnet => write!(f, "testnet"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Named rpc endpoint, overrides the rpc url and commitment of the solana CLI config.
+/// If cluster is set, the endpoint has to serve that cluster.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterProfile {
+    pub rpc_url: String,
+    pub cluster: Option<Cluster>,
+    #[serde(default = "default_commitment")]
+    pub commitment: CommitmentLevel,
+}
+
+fn default_commitment() -> CommitmentLevel {
+    CommitmentLevel::Confirmed
+}
+
+impl ClusterProfile {
+    pub fn commitment_config(&self) -> CommitmentConfig {
+        CommitmentConfig {
+            commitment: self.commitment,
+        }
+    }
+}
+
+/// Project config file with named cluster profiles
+///
+/// e.g.
+/// ```json
+/// {
+///   "profiles": {
+///     "devnet": { "rpc_url": "https://api.devnet.solana.com", "cluster": "devnet" },
+///     "mainnet": { "rpc_url": "https://api.mainnet-beta.solana.com", "cluster": "mainnet-beta", "commitment": "finalized" }
+///   }
+/// }
+/// ```
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub profiles: HashMap<String, ClusterProfile>,
+}
+
+impl ProjectConfig {
+    pub fn parse_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing project config from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let config = serde_json::from_str(&data)?;
+        log::info!("Finished parsing project config");
+        Ok(config)
+    }
+
+    pub fn take_profile(mut self, name: &str) -> Result<ClusterProfile> {
+        self.profiles
+            .remove(name)
+            .ok_or_else(|| Error::ProfileNotFound(name.to_owned()))
+    }
+}
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use solana_account_decoder::parse_token::{UiAccountState, UiTokenAccount};
+use solana_sdk::{account::Account, system_program};
+
+use crate::errors::Result;
+
+use super::DisqualificationReason;
+
+/// Additional rules an existing token account (and its wallet) has to pass to qualify for airdrop.
+/// Every rule is disabled if not set.
+///
+/// e.g.
+/// ```json
+/// {
+///   "min_sol_balance_lamports": 1000000,
+///   "not_frozen": true,
+///   "min_token_balance_atomic": 1,
+///   "wallet_system_owned": true
+/// }
+/// ```
+#[derive(Debug, Default, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QualificationRules {
+    pub min_sol_balance_lamports: Option<u64>,
+    /// frozen token accounts are always disqualified (a transfer into one cannot succeed),
+    /// kept so that existing rule files still parse
+    pub not_frozen: bool,
+    /// destination token accounts always have to be owned by the wallet,
+    /// kept so that existing rule files still parse
+    #[allow(dead_code)]
+    pub owner_is_wallet: bool,
+    pub min_token_balance_atomic: Option<u64>,
+    pub wallet_system_owned: bool,
+}
+
+impl QualificationRules {
+    pub fn parse_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing qualification rules from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let rules = serde_json::from_str(&data)?;
+        log::info!("Finished parsing qualification rules: {rules:?}");
+        Ok(rules)
+    }
+
+    // wallet accounts only need to be fetched for rules that look at the wallet itself
+    pub fn requires_wallet_account(&self) -> bool {
+        self.min_sol_balance_lamports.is_some() || self.wallet_system_owned
+    }
+
+    /// Returns the reason of the first failing rule
+    // NOTE: a wallet that does not exist on chain is treated as system owned with 0 lamports
+    pub fn evaluate(
+        &self,
+        token_account: &UiTokenAccount,
+        wallet_account: Option<&Account>,
+    ) -> std::result::Result<(), DisqualificationReason> {
+        if let Some(min_sol_balance_lamports) = self.min_sol_balance_lamports {
+            let lamports = wallet_account.map(|account| account.lamports).unwrap_or(0);
+            if lamports < min_sol_balance_lamports {
+                return Err(DisqualificationReason::InsufficientSolBalance);
+            }
+        }
+        if self.not_frozen && matches!(token_account.state, UiAccountState::Frozen) {
+            return Err(DisqualificationReason::Frozen);
+        }
+        if let Some(min_token_balance_atomic) = self.min_token_balance_atomic {
+            let balance: u64 = token_account.token_amount.amount.parse().unwrap_or(0);
+            if balance < min_token_balance_atomic {
+                return Err(DisqualificationReason::InsufficientTokenBalance);
+            }
+        }
+        if self.wallet_system_owned
+            && wallet_account.is_some_and(|account| account.owner != system_program::ID)
+        {
+            return Err(DisqualificationReason::NotSystemOwned);
+        }
+        Ok(())
+    }
+
+    /// Same as evaluate, for a token account that does not exist yet (to be created on send)
+    pub fn evaluate_missing_token_account(
+        &self,
+        wallet_account: Option<&Account>,
+    ) -> std::result::Result<(), DisqualificationReason> {
+        if let Some(min_sol_balance_lamports) = self.min_sol_balance_lamports {
+            let lamports = wallet_account.map(|account| account.lamports).unwrap_or(0);
+            if lamports < min_sol_balance_lamports {
+                return Err(DisqualificationReason::InsufficientSolBalance);
+            }
+        }
+        if self
+            .min_token_balance_atomic
+            .is_some_and(|min_token_balance_atomic| min_token_balance_atomic > 0)
+        {
+            return Err(DisqualificationReason::InsufficientTokenBalance);
+        }
+        if self.wallet_system_owned
+            && wallet_account.is_some_and(|account| account.owner != system_program::ID)
+        {
+            return Err(DisqualificationReason::NotSystemOwned);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rule_files_with_always_enforced_fields() {
+        let rules: QualificationRules = serde_json::from_str(
+            r#"{ "not_frozen": true, "owner_is_wallet": true, "min_token_balance_atomic": 1 }"#,
+        )
+        .unwrap();
+        assert_eq!(rules.min_token_balance_atomic, Some(1));
+        assert!(serde_json::from_str::<QualificationRules>(r#"{ "unknown": true }"#).is_err());
+    }
+}
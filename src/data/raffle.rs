@@ -0,0 +1,184 @@
+use std::path::PathBuf;
+
+use solana_sdk::hash::{hashv, Hasher};
+
+use crate::{errors::Result, utils::to_hex};
+
+use super::{CsvEntrySer, Snapshot, SnapshotEntry};
+
+/// Each draw i takes sha256(seed || i as u64 le), interprets its first 16 bytes as a u128 (le) and picks
+/// the remaining snapshot entry (sorted by wallet_pubkey) whose cumulative balance range contains
+/// (that value % remaining total balance). Winners are removed before the next draw.
+pub const RAFFLE_ALGORITHM: &str = "sha256-weighted-draw-without-replacement-v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RaffleSeedSource {
+    /// seed is the blockhash of the first block at or after the requested slot
+    Blockhash {
+        /// finalized slot when the draw started, informational only: it is reported by whoever ran
+        /// the raffle and can't be verified from the proof
+        draw_start_slot: u64,
+        requested_slot: u64,
+        slot: u64,
+        blockhash: String,
+    },
+    Hex,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RaffleDraw {
+    pub index: u64,
+    pub wallet_pubkey: String,
+    pub token_balance_atomic: u64,
+    // NOTE: u128 values are stored as strings so that the proof can be read without precision loss
+    pub remaining_total_balance: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RaffleProof {
+    pub algorithm: String,
+    pub seed: String,
+    pub seed_source: RaffleSeedSource,
+    /// sha256 over the csv records of the snapshot (sorted by wallet_pubkey)
+    pub snapshot_hash: String,
+    pub number_of_winners: u64,
+    pub amount_per_winner: u64,
+    pub draws: Vec<RaffleDraw>,
+}
+
+impl RaffleProof {
+    pub fn parse_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing raffle proof from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let proof = serde_json::from_str(&data)?;
+        log::info!("Finished parsing raffle proof");
+        Ok(proof)
+    }
+
+    pub fn save_to_path(&self, path: &PathBuf) -> Result<()> {
+        log::info!("Saving raffle proof to {path:?} ...");
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::info!("Finished saving raffle proof");
+        Ok(())
+    }
+}
+
+/// NOTE: assumes snapshot is sorted by wallet_pubkey (guaranteed by Snapshot::parse_list_from_path)
+pub fn hash_snapshot(snapshot: &Snapshot) -> String {
+    let mut hasher = Hasher::default();
+    for entry in snapshot.0.iter() {
+        hasher.hash(entry.to_record().join(",").as_bytes());
+        hasher.hash(b"\n");
+    }
+    to_hex(hasher.result().as_ref())
+}
+
+/// NOTE: assumes snapshot is sorted by wallet_pubkey (guaranteed by Snapshot::parse_list_from_path)
+pub fn draw_winners(snapshot: &Snapshot, seed: &[u8], number_of_winners: u64) -> Vec<RaffleDraw> {
+    let mut candidates: Vec<&SnapshotEntry> = snapshot.0.iter().collect();
+    let mut remaining_total_balance: u128 = candidates
+        .iter()
+        .map(|entry| entry.token_balance_atomic as u128)
+        .sum();
+
+    let mut draws = Vec::new();
+    for index in 0..number_of_winners {
+        if remaining_total_balance == 0 {
+            break;
+        }
+        let random = hashv(&[seed, &index.to_le_bytes()]).to_bytes();
+        // UNWRAP-SAFETY: hash is 32 bytes long
+        let target =
+            u128::from_le_bytes(random[..16].try_into().unwrap()) % remaining_total_balance;
+
+        let mut cumulative_balance: u128 = 0;
+        let position = candidates
+            .iter()
+            .position(|entry| {
+                cumulative_balance += entry.token_balance_atomic as u128;
+                cumulative_balance > target
+            })
+            .expect("target is always less than remaining total balance");
+        let winner = candidates.remove(position);
+
+        draws.push(RaffleDraw {
+            index,
+            wallet_pubkey: winner.wallet_pubkey.to_string(),
+            token_balance_atomic: winner.token_balance_atomic,
+            remaining_total_balance: remaining_total_balance.to_string(),
+            target: target.to_string(),
+        });
+        remaining_total_balance -= winner.token_balance_atomic as u128;
+    }
+    draws
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+
+    fn snapshot(balances: &[u64]) -> Snapshot {
+        Snapshot(
+            balances
+                .iter()
+                .enumerate()
+                .map(|(i, token_balance_atomic)| SnapshotEntry {
+                    wallet_pubkey: Pubkey::new_from_array([i as u8 + 1; 32]),
+                    token_balance_atomic: *token_balance_atomic,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn draw_winners_matches_fixed_draws() {
+        // NOTE: computed independently from the RAFFLE_ALGORITHM description
+        let draws = draw_winners(&snapshot(&[10, 20, 70]), b"seed", 3);
+        let expected = [
+            (3u8, 70, "100", "98"),
+            (2, 20, "30", "17"),
+            (1, 10, "10", "7"),
+        ];
+        assert_eq!(draws.len(), expected.len());
+        for (index, (draw, (wallet, balance, remaining, target))) in
+            draws.iter().zip(expected).enumerate()
+        {
+            assert_eq!(draw.index, index as u64);
+            assert_eq!(
+                draw.wallet_pubkey,
+                Pubkey::new_from_array([wallet; 32]).to_string()
+            );
+            assert_eq!(draw.token_balance_atomic, balance);
+            assert_eq!(draw.remaining_total_balance, remaining);
+            assert_eq!(draw.target, target);
+        }
+    }
+
+    #[test]
+    fn draw_winners_is_deterministic_and_without_replacement() {
+        let snapshot = snapshot(&[5, 0, 1, 100, 0, 42]);
+        let draws = draw_winners(&snapshot, &[7; 32], 10);
+        assert_eq!(draws, draw_winners(&snapshot, &[7; 32], 10));
+        // wallets without balance are never drawn
+        assert_eq!(draws.len(), 4);
+        let mut winners: Vec<&str> = draws
+            .iter()
+            .map(|draw| draw.wallet_pubkey.as_str())
+            .collect();
+        winners.sort_unstable();
+        winners.dedup();
+        assert_eq!(winners.len(), 4);
+        assert!(draws.iter().all(|draw| draw.token_balance_atomic != 0));
+    }
+
+    #[test]
+    fn draw_winners_stops_at_number_of_winners() {
+        let draws = draw_winners(&snapshot(&[1, 2, 3]), b"seed", 2);
+        assert_eq!(draws.len(), 2);
+        assert!(draw_winners(&snapshot(&[1, 2, 3]), b"seed", 0).is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    errors::{Error, Result},
+    utils::add_to_filename,
+};
+
+use super::SentTx;
+
+/// A batch of entries, recorded right before its tx is submitted
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SendJournalRecord {
+    /// idxs of the entries in the (sorted) sent stage file
+    pub idxs: Vec<usize>,
+    /// wallet pubkeys of the entries, to make sure the idxs still point at them
+    pub wallet_pubkeys: Vec<String>,
+    pub signature: String,
+    pub sent_tx: SentTx,
+    pub compute_unit_price: u64,
+}
+
+/// Append-only journal (one json record per line) of the batches submitted by an ongoing send.
+/// Every record is synced to disk before its tx is submitted, so that an interrupted send can resume
+/// from the sent stage checkpoint without losing any signature
+#[derive(Debug)]
+pub struct SendJournal {
+    file: File,
+}
+
+impl SendJournal {
+    // e.g. list.csv -> list.send_journal.jsonl
+    pub fn path_from_wallet_list_path(wallet_list_path: &Path) -> PathBuf {
+        add_to_filename(wallet_list_path, "send_journal").with_extension("jsonl")
+    }
+
+    /// starts an empty journal (records of an earlier journal are in the checkpoint by then)
+    pub fn create(path: &PathBuf) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self { file })
+    }
+
+    pub fn append(&mut self, record: &SendJournalRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    pub fn parse_from_path(path: &PathBuf) -> Result<Vec<SendJournalRecord>> {
+        log::info!("Parsing send journal from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let lines: Vec<&str> = data.lines().filter(|line| !line.is_empty()).collect();
+        let mut records = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                // NOTE: the last line might have been cut off by a crash, its tx was never submitted then
+                Err(err) if i == lines.len() - 1 => {
+                    log::warn!("Ignoring incomplete last record of send journal: {err}")
+                }
+                Err(err) => return Err(Error::SendJournalError(format!("line {}: {err}", i + 1))),
+            }
+        }
+        log::info!("Finished parsing send journal");
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::Signature;
+
+    use super::*;
+
+    fn record(n: u8) -> SendJournalRecord {
+        SendJournalRecord {
+            idxs: vec![n as usize],
+            wallet_pubkeys: vec![solana_sdk::pubkey::Pubkey::new_from_array([n; 32]).to_string()],
+            signature: Signature::from([n; 64]).to_string(),
+            sent_tx: SentTx {
+                tx: String::new(),
+                last_valid_block_height: n as u64,
+                nonce_account: None,
+            },
+            compute_unit_price: n as u64,
+        }
+    }
+
+    // writes given records followed by the raw tail, returns the journal path
+    fn write_journal(name: &str, records: &[SendJournalRecord], tail: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "airdropper-{name}-{}.send_journal.jsonl",
+            std::process::id()
+        ));
+        let mut journal = SendJournal::create(&path).unwrap();
+        for record in records {
+            journal.append(record).unwrap();
+        }
+        journal.file.write_all(tail.as_bytes()).unwrap();
+        path
+    }
+
+    #[test]
+    fn ignores_a_truncated_last_record() {
+        let line = serde_json::to_string(&record(3)).unwrap();
+        let path = write_journal(
+            "truncated",
+            &[record(1), record(2)],
+            &line[..line.len() / 2],
+        );
+        let records = SendJournal::parse_from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            records.iter().map(|r| r.idxs[0]).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(records[1].signature, record(2).signature);
+    }
+
+    #[test]
+    fn rejects_a_corrupt_record_before_the_last_one() {
+        let mut tail = "{\"idxs\":[\n".to_string();
+        tail.push_str(&serde_json::to_string(&record(3)).unwrap());
+        tail.push('\n');
+        let path = write_journal("corrupt", &[record(1)], &tail);
+        let res = SendJournal::parse_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(res, Err(Error::SendJournalError(msg)) if msg.starts_with("line 2:")));
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{
+    hash::Hash, pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction,
+};
+
+use crate::{
+    errors::{Error, Result},
+    utils::{add_to_filename, write_file_atomically},
+};
+
+/// A signed tx as it was sent, together with the last block height its blockhash is valid for
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SentTx {
+    /// base64 encoded (bincode) signed tx
+    pub tx: String,
+    /// u64::MAX for durable nonce txs, which stay valid until their nonce advances
+    pub last_valid_block_height: u64,
+    #[serde(default)]
+    pub nonce_account: Option<String>,
+}
+
+impl SentTx {
+    pub fn new(
+        tx: &VersionedTransaction,
+        last_valid_block_height: u64,
+        nonce_account: Option<&Pubkey>,
+    ) -> Self {
+        let data = bincode::serialize(tx).expect("Could not serialize tx");
+        Self {
+            tx: STANDARD.encode(data),
+            last_valid_block_height,
+            nonce_account: nonce_account.map(|pubkey| pubkey.to_string()),
+        }
+    }
+}
+
+/// Signed txs sent by send (keyed by signature), kept so that an unconfirmed tx is rebroadcast
+/// as is (same signature) instead of being rebuilt while it still might land
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct SentTxList {
+    pub txs: HashMap<String, SentTx>,
+}
+
+impl SentTxList {
+    // e.g. list.csv -> list.sent_txs.json
+    pub fn path_from_wallet_list_path(wallet_list_path: &Path) -> PathBuf {
+        add_to_filename(wallet_list_path, "sent_txs").with_extension("json")
+    }
+
+    pub fn parse_from_path(path: &PathBuf) -> Result<Self> {
+        log::info!("Parsing sent tx list from {path:?} ...");
+        let data = std::fs::read_to_string(path)?;
+        let list = serde_json::from_str(&data)?;
+        log::info!("Finished parsing sent tx list");
+        Ok(list)
+    }
+
+    pub fn save_to_path(&self, path: &PathBuf) -> Result<()> {
+        log::info!("Saving sent tx list to {path:?} ...");
+        write_file_atomically(path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        log::info!("Finished saving sent tx list");
+        Ok(())
+    }
+
+    pub fn insert(&mut self, sig: &Signature, sent_tx: SentTx) {
+        self.txs.insert(sig.to_string(), sent_tx);
+    }
+
+    pub fn last_valid_block_height(&self, sig: &Signature) -> Option<u64> {
+        self.txs
+            .get(&sig.to_string())
+            .map(|sent_tx| sent_tx.last_valid_block_height)
+    }
+
+    /// (nonce account, durable nonce) of given signature if it was sent as a durable nonce tx
+    pub fn durable_nonce(&self, sig: &Signature) -> Result<Option<(Pubkey, Hash)>> {
+        let Some(nonce_account) = self
+            .txs
+            .get(&sig.to_string())
+            .and_then(|sent_tx| sent_tx.nonce_account.as_deref())
+        else {
+            return Ok(None);
+        };
+        let nonce = match self.get(sig)? {
+            Some((tx, _)) => *tx.message.recent_blockhash(),
+            None => return Ok(None),
+        };
+        Ok(Some((Pubkey::from_str(nonce_account)?, nonce)))
+    }
+
+    /// (signed tx, last valid block height) of given signature
+    pub fn get(&self, sig: &Signature) -> Result<Option<(VersionedTransaction, u64)>> {
+        let Some(SentTx {
+            tx,
+            last_valid_block_height,
+            ..
+        }) = self.txs.get(&sig.to_string())
+        else {
+            return Ok(None);
+        };
+        let data = STANDARD
+            .decode(tx)
+            .map_err(|err| Error::TxDecodeError(err.to_string()))?;
+        let tx =
+            bincode::deserialize(&data).map_err(|err| Error::TxDecodeError(err.to_string()))?;
+        Ok(Some((tx, *last_valid_block_height)))
+    }
+}
@@ -3,24 +3,48 @@ use std::{
     fmt::Display,
     path::PathBuf,
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use solana_client::rpc_client::{RpcClient, SerializableTransaction};
+use solana_client::{
+    rpc_client::{RpcClient, SerializableTransaction},
+    rpc_config::RpcSendTransactionConfig,
+};
 use solana_program::{instruction::Instruction, pubkey::Pubkey};
-use solana_sdk::{signature::Signature, signer::Signer};
-use spl_associated_token_account::get_associated_token_address_with_program_id;
-use spl_token_2022::instruction::transfer_checked;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, signature::Signature, signer::Signer,
+    system_instruction::advance_nonce_account, transaction::VersionedTransaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_memo::build_memo;
+use spl_token_2022::{
+    extension::transfer_fee::{instruction::transfer_checked_with_fee, TransferFee},
+    instruction::transfer_checked,
+};
 
 use crate::{
-    consts::{ATA_GET_MULT_ACC_CHUNK_SIZE, TRANSFER_IXS_CHUNK_SIZE},
+    consts::{AIRDROP_MEMO, ATA_GET_MULT_ACC_CHUNK_SIZE},
     errors::{Error, Result},
     utils::{
-        check_atas, confirm_signature, create_backup_if_file_exists, get_compute_budget_ixs,
-        prep_tx,
+        acquire_nonce_account, check_atas, confirm_signature, copy_backup_if_file_exists,
+        find_largest_token_accounts, get_compute_budget_ixs, get_durable_nonce, get_send_backoff,
+        is_interrupted, prep_tx_with_blockhash, simulate_compute_unit_limit, submit_tx,
+        write_file_atomically, AtaCheck, BlockhashCache, EpochCache, MintExtensions, NoncePool,
+        SendOptions, TransferHookResolver,
     },
 };
 
-use super::{CsvEntrySer, CsvListSerde};
+use super::{
+    CsvEntrySer, CsvListSerde, NonceAccountList, QualificationRules, SendJournal,
+    SendJournalRecord, SentTx, SentTxList,
+};
 
 // TODO: use serde with
 #[derive(Debug, serde::Deserialize, Clone)]
@@ -33,15 +57,98 @@ struct WalletListEntryRaw {
     pub status: Option<String>,
     #[serde(default)]
     pub status_inner: Option<String>,
+    #[serde(default)]
+    pub source_balance_atomic: Option<u64>,
+    #[serde(default)]
+    pub weight: Option<f64>,
+    #[serde(default)]
+    pub multiplier: Option<f64>,
+    #[serde(default)]
+    pub bound_applied: Option<String>,
+    #[serde(default)]
+    pub rounding_adjustment: Option<f64>,
+    #[serde(default)]
+    pub ata_kind: Option<String>,
+    #[serde(default)]
+    pub memo_required: Option<bool>,
+    #[serde(default)]
+    pub compute_unit_price: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisqualificationReason {
+    // disqualified before reasons were recorded
+    Unknown,
+    NoAccount,
+    NotTokenAccount,
+    MintMismatch,
+    Frozen,
+    OwnerMismatch,
+    InsufficientSolBalance,
+    InsufficientTokenBalance,
+    NotSystemOwned,
+    NonTransferable,
+    DefaultFrozen,
+}
+
+impl DisqualificationReason {
+    fn to_record(self) -> Option<String> {
+        let reason = match self {
+            Self::Unknown => return None,
+            Self::NoAccount => "no_account",
+            Self::NotTokenAccount => "not_token_account",
+            Self::MintMismatch => "mint_mismatch",
+            Self::Frozen => "frozen",
+            Self::OwnerMismatch => "owner_mismatch",
+            Self::InsufficientSolBalance => "insufficient_sol_balance",
+            Self::InsufficientTokenBalance => "insufficient_token_balance",
+            Self::NotSystemOwned => "not_system_owned",
+            Self::NonTransferable => "non_transferable",
+            Self::DefaultFrozen => "default_frozen",
+        };
+        Some(reason.to_string())
+    }
+
+    fn try_from_raw(value: Option<String>) -> Result<Self> {
+        let reason = match value.as_deref() {
+            None => Self::Unknown,
+            Some("no_account") => Self::NoAccount,
+            Some("not_token_account") => Self::NotTokenAccount,
+            Some("mint_mismatch") => Self::MintMismatch,
+            Some("frozen") => Self::Frozen,
+            Some("owner_mismatch") => Self::OwnerMismatch,
+            Some("insufficient_sol_balance") => Self::InsufficientSolBalance,
+            Some("insufficient_token_balance") => Self::InsufficientTokenBalance,
+            Some("not_system_owned") => Self::NotSystemOwned,
+            Some("non_transferable") => Self::NonTransferable,
+            Some("default_frozen") => Self::DefaultFrozen,
+            Some(value) => {
+                panic!("Wrong arg was given to DisqualificationReason::try_from_raw: {value}")
+            }
+        };
+        Ok(reason)
+    }
+}
+
+impl Display for DisqualificationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.to_record().unwrap_or_else(|| "unknown".to_string())
+        )
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 pub enum Status {
     #[default]
     Unprocessed,
-    Disqualified,
+    Disqualified(DisqualificationReason),
     Qualified,
     Unconfirmed(Signature),
+    // never landed and its blockhash expired, safe to retry
+    Expired(Signature),
     Failed(String),
     Succeeded(Signature),
     Excluded(String),
@@ -51,21 +158,32 @@ impl Status {
     fn to_record(&self) -> (String, Option<String>) {
         match self {
             Self::Unprocessed => ("unprocessed".to_string(), None),
-            Self::Disqualified => ("disqualified".to_string(), None),
+            Self::Disqualified(reason) => ("disqualified".to_string(), reason.to_record()),
             Self::Qualified => ("qualified".to_string(), None),
             Self::Unconfirmed(sig) => ("unconfirmed".to_string(), Some(sig.to_string())),
+            Self::Expired(sig) => ("expired".to_string(), Some(sig.to_string())),
             Self::Failed(err) => ("failed".to_string(), Some(err.to_string())),
             Self::Succeeded(sig) => ("succeeded".to_string(), Some(sig.to_string())),
             Self::Excluded(err) => ("excluded".to_string(), Some(err.to_string())),
         }
     }
 
+    pub fn signature(&self) -> Option<Signature> {
+        match self {
+            Self::Unconfirmed(sig) | Self::Succeeded(sig) => Some(*sig),
+            _ => None,
+        }
+    }
+
     fn try_from_raw(value: &str, inner_value: Option<String>) -> Result<Self> {
         let status = match (value, inner_value) {
             ("unprocessed", None) => Self::Unprocessed,
-            ("disqualified", None) => Self::Disqualified,
+            ("disqualified", reason) => {
+                Self::Disqualified(DisqualificationReason::try_from_raw(reason)?)
+            }
             ("qualified", None) => Self::Qualified,
             ("unconfirmed", Some(sig)) => Self::Unconfirmed(Signature::from_str(&sig)?),
+            ("expired", Some(sig)) => Self::Expired(Signature::from_str(&sig)?),
             ("failed", Some(err)) => Self::Failed(err),
             ("succeeded", Some(sig)) => Self::Succeeded(Signature::from_str(&sig)?),
             ("excluded", Some(err)) => Self::Excluded(err),
@@ -83,24 +201,188 @@ impl Display for Status {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BoundApplied {
+    #[default]
+    None,
+    Cap,
+    Floor,
+}
+
+impl BoundApplied {
+    fn to_record(self) -> String {
+        match self {
+            Self::None => "".to_string(),
+            Self::Cap => "cap".to_string(),
+            Self::Floor => "floor".to_string(),
+        }
+    }
+
+    fn try_from_raw(value: Option<String>) -> Result<Self> {
+        let bound_applied = match value.as_deref() {
+            None | Some("") => Self::None,
+            Some("cap") => Self::Cap,
+            Some("floor") => Self::Floor,
+            Some(value) => {
+                panic!("Wrong arg was given to BoundApplied::try_from_raw: {value}")
+            }
+        };
+        Ok(bound_applied)
+    }
+}
+
+impl Display for BoundApplied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            _ => write!(f, "{}", self.to_record()),
+        }
+    }
+}
+
+/// Where the destination token account (ata column) of an entry came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtaKind {
+    /// derived associated token account that exists
+    Derived,
+    /// derived associated token account that does not exist yet and is created on send
+    Create,
+    /// existing non-associated token account of the wallet, used since the ata does not exist
+    Discovered,
+}
+
+impl AtaKind {
+    fn to_record(self) -> String {
+        match self {
+            Self::Derived => "derived".to_string(),
+            Self::Create => "create".to_string(),
+            Self::Discovered => "discovered".to_string(),
+        }
+    }
+
+    // NOTE: None means the ata was given by the wallet list
+    fn try_from_raw(value: Option<String>) -> Result<Option<Self>> {
+        let ata_kind = match value.as_deref() {
+            None | Some("") => None,
+            Some("derived") => Some(Self::Derived),
+            Some("create") => Some(Self::Create),
+            Some("discovered") => Some(Self::Discovered),
+            Some(value) => {
+                panic!("Wrong arg was given to AtaKind::try_from_raw: {value}")
+            }
+        };
+        Ok(ata_kind)
+    }
+}
+
+/// How the amount_to_airdrop of an entry was derived from the snapshot
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    /// token balance (in token atomic) of the wallet in the snapshot
+    pub source_balance_atomic: u64,
+    /// share of the total (multiplied) snapshot balance
+    pub weight: f64,
+    pub multiplier: f64,
+    pub bound_applied: BoundApplied,
+    /// final amount - exact pro rata amount (in token atomic), before any bound is applied
+    pub rounding_adjustment: f64,
+}
+
+impl Provenance {
+    fn to_record(&self) -> Vec<String> {
+        vec![
+            self.source_balance_atomic.to_string(),
+            self.weight.to_string(),
+            self.multiplier.to_string(),
+            self.bound_applied.to_record(),
+            self.rounding_adjustment.to_string(),
+        ]
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct WalletListEntry {
     pub wallet_pubkey: Pubkey,
     pub amount_to_airdrop: u64,
     pub ata: Option<Pubkey>,
     pub status: Status,
+    // NOTE: None for wallet lists that were not generated by wallet-list subcmd
+    pub provenance: Option<Provenance>,
+    pub ata_kind: Option<AtaKind>,
+    // destination requires a memo ix right before the transfer (token-2022 MemoTransfer extension)
+    pub memo_required: bool,
+    // compute unit price (in micro lamports) of the last tx the entry was sent in
+    pub compute_unit_price: Option<u64>,
 }
 
 impl CsvEntrySer for WalletListEntry {
     fn to_record(&self) -> Vec<String> {
         let (status, status_inner) = self.status.to_record();
-        vec![
+        let provenance = self
+            .provenance
+            .as_ref()
+            .map(|provenance| provenance.to_record())
+            .unwrap_or_else(|| vec!["".to_string(); 5]);
+        [
             self.wallet_pubkey.to_string(),
             self.amount_to_airdrop.to_string(),
             self.ata.map(|pk| pk.to_string()).unwrap_or("".to_string()),
             status.to_string(),
             status_inner.unwrap_or("".to_string()),
         ]
+        .into_iter()
+        .chain(provenance)
+        .chain([self
+            .ata_kind
+            .map(|ata_kind| ata_kind.to_record())
+            .unwrap_or("".to_string())])
+        .chain([if self.memo_required {
+            true.to_string()
+        } else {
+            "".to_string()
+        }])
+        .chain([self
+            .compute_unit_price
+            .map(|price| price.to_string())
+            .unwrap_or("".to_string())])
+        .collect()
+    }
+}
+
+impl Display for WalletListEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (status, status_inner) = self.status.to_record();
+        writeln!(f, "wallet_pubkey: {}", self.wallet_pubkey)?;
+        writeln!(f, "amount_to_airdrop: {}", self.amount_to_airdrop)?;
+        writeln!(
+            f,
+            "ata: {} ({})",
+            self.ata.map(|pk| pk.to_string()).unwrap_or_default(),
+            self.ata_kind
+                .map(|ata_kind| ata_kind.to_record())
+                .unwrap_or("given".to_string())
+        )?;
+        writeln!(f, "status: {status} {}", status_inner.unwrap_or_default())?;
+        writeln!(f, "memo_required: {}", self.memo_required)?;
+        if let Some(compute_unit_price) = self.compute_unit_price {
+            writeln!(f, "compute_unit_price: {compute_unit_price}")?;
+        }
+        match &self.provenance {
+            Some(Provenance {
+                source_balance_atomic,
+                weight,
+                multiplier,
+                bound_applied,
+                rounding_adjustment,
+            }) => {
+                writeln!(f, "source_balance_atomic: {source_balance_atomic}")?;
+                writeln!(f, "weight: {weight}")?;
+                writeln!(f, "multiplier: {multiplier}")?;
+                writeln!(f, "bound_applied: {bound_applied}")?;
+                write!(f, "rounding_adjustment: {rounding_adjustment}")
+            }
+            None => write!(f, "provenance: unknown"),
+        }
     }
 }
 
@@ -125,6 +407,12 @@ impl WalletListEntry {
         }
     }
 
+    fn set_unconfirmed_to_expired(&mut self) {
+        if let Status::Unconfirmed(sig) = &self.status {
+            self.status = Status::Expired(sig.to_owned());
+        }
+    }
+
     fn set_unconfirmed_to_succeeded(&mut self) {
         if let Status::Unconfirmed(sig) = &self.status {
             self.status = Status::Succeeded(sig.to_owned());
@@ -138,33 +426,120 @@ impl WalletListEntry {
                 token_mint_pubkey,
                 token_program_id,
             ));
+            self.ata_kind = Some(AtaKind::Derived);
         }
     }
 
-    pub fn to_transfer_ix(
+    fn apply_ata_check(
+        &mut self,
+        AtaCheck {
+            status,
+            memo_required,
+            extension_types,
+            ..
+        }: AtaCheck,
+        extension_counts: &mut HashMap<String, usize>,
+    ) {
+        if matches!(status, Status::Qualified) {
+            for extension_type in extension_types {
+                *extension_counts.entry(extension_type).or_default() += 1;
+            }
+        }
+        self.memo_required = memo_required;
+        self.status = status;
+    }
+
+    pub fn to_create_ata_ix(
         &self,
         token_mint_pubkey: &Pubkey,
         token_program_id: &Pubkey,
-        token_decimals: u8,
-        source_ata: &Pubkey,
         payer: &dyn Signer,
     ) -> Instruction {
-        transfer_checked(
-            token_program_id,
-            source_ata,
-            token_mint_pubkey,
-            &self.ata.unwrap(),
+        create_associated_token_account_idempotent(
             &payer.pubkey(),
-            &[&payer.pubkey()],
-            self.amount_to_airdrop,
-            token_decimals,
+            &self.wallet_pubkey,
+            token_mint_pubkey,
+            token_program_id,
         )
+    }
+
+    // returns (amount to transfer, fee withheld from it)
+    // NOTE: when grossing up, the fee is added on top so that the wallet receives amount_to_airdrop
+    pub fn transfer_amount_and_fee(
+        &self,
+        transfer_fee: Option<&TransferFee>,
+        gross_up_transfer_fee: bool,
+    ) -> (u64, u64) {
+        let Some(transfer_fee) = transfer_fee else {
+            return (self.amount_to_airdrop, 0);
+        };
+        let amount = if gross_up_transfer_fee {
+            transfer_fee
+                .calculate_pre_fee_amount(self.amount_to_airdrop)
+                .unwrap_or_else(|| panic!("Could not gross up transfer amount: {self:?}"))
+        } else {
+            self.amount_to_airdrop
+        };
+        let fee = transfer_fee
+            .calculate_fee(amount)
+            .unwrap_or_else(|| panic!("Could not calculate transfer fee: {self:?}"));
+        (amount, fee)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn to_transfer_ix(
+        &self,
+        token_mint_pubkey: &Pubkey,
+        token_program_id: &Pubkey,
+        token_decimals: u8,
+        source_ata: &Pubkey,
+        payer: &dyn Signer,
+        transfer_fee: Option<&TransferFee>,
+        gross_up_transfer_fee: bool,
+        transfer_hook_resolver: Option<&TransferHookResolver>,
+    ) -> Result<Instruction> {
+        let (amount, fee) = self.transfer_amount_and_fee(transfer_fee, gross_up_transfer_fee);
+        let mut transfer_ix = match transfer_fee {
+            // NOTE: fee has to match the fee calculated by the token program for the current epoch
+            Some(_) => transfer_checked_with_fee(
+                token_program_id,
+                source_ata,
+                token_mint_pubkey,
+                &self.ata.unwrap(),
+                &payer.pubkey(),
+                &[&payer.pubkey()],
+                amount,
+                token_decimals,
+                fee,
+            ),
+            None => transfer_checked(
+                token_program_id,
+                source_ata,
+                token_mint_pubkey,
+                &self.ata.unwrap(),
+                &payer.pubkey(),
+                &[&payer.pubkey()],
+                amount,
+                token_decimals,
+            ),
+        }
         .unwrap_or_else(|_| {
             // NOTE:
             //  - normally, this should never happen since transfer_checked can only error out when incorrect program id was given.
             //  - if this errors out, then given data of wallet list has to contain a wrong line.
             panic!("This should not happen unless given wallet list data was wrong: {self:?}");
-        })
+        });
+        if let Some(transfer_hook_resolver) = transfer_hook_resolver {
+            transfer_hook_resolver.add_extra_account_metas(
+                &mut transfer_ix,
+                source_ata,
+                token_mint_pubkey,
+                &self.ata.unwrap(),
+                &payer.pubkey(),
+                amount,
+            )?;
+        }
+        Ok(transfer_ix)
     }
 }
 
@@ -178,6 +553,14 @@ impl TryFrom<WalletListEntryRaw> for WalletListEntry {
             ata,
             status,
             status_inner,
+            source_balance_atomic,
+            weight,
+            multiplier,
+            bound_applied,
+            rounding_adjustment,
+            ata_kind,
+            memo_required,
+            compute_unit_price,
         }: WalletListEntryRaw,
     ) -> std::prelude::v1::Result<Self, Self::Error> {
         let wallet_pubkey = Pubkey::from_str(&wallet_pubkey)?;
@@ -186,15 +569,160 @@ impl TryFrom<WalletListEntryRaw> for WalletListEntry {
             &status.unwrap_or_else(|| Status::default().to_record().0),
             status_inner,
         )?;
+        let provenance = match source_balance_atomic {
+            Some(source_balance_atomic) => Some(Provenance {
+                source_balance_atomic,
+                weight: weight.unwrap_or_default(),
+                multiplier: multiplier.unwrap_or(1.0),
+                bound_applied: BoundApplied::try_from_raw(bound_applied)?,
+                rounding_adjustment: rounding_adjustment.unwrap_or_default(),
+            }),
+            None => None,
+        };
+        let ata_kind = match ata {
+            Some(_) => AtaKind::try_from_raw(ata_kind)?,
+            None => None,
+        };
         Ok(Self {
             wallet_pubkey,
             amount_to_airdrop,
             ata,
             status,
+            provenance,
+            ata_kind,
+            memo_required: memo_required.unwrap_or(false),
+            compute_unit_price,
         })
     }
 }
 
+// ((idx, wallet pubkey), ixs) of entries sent in a single tx
+pub type TransferBatch = Vec<((usize, Pubkey), Vec<Instruction>)>;
+
+/// Where transfer_airdrop records the txs it sends (before they are submitted)
+#[derive(Debug)]
+pub struct SendRecords<'a> {
+    pub sent_tx_list: &'a mut SentTxList,
+    /// Some in durable nonce mode
+    pub nonce_account_list: Option<&'a mut NonceAccountList>,
+    pub send_journal: Option<&'a mut SendJournal>,
+}
+
+// a batch to be built into a tx by a prepare worker
+#[derive(Debug)]
+struct TransferJob {
+    idxs: Vec<usize>,
+    wallet_pubkeys: Vec<Pubkey>,
+    transfer_ixs: Vec<Instruction>,
+    previous_compute_unit_price: Option<u64>,
+}
+
+// a signed tx of a batch, to be recorded and submitted
+#[derive(Debug)]
+struct PreparedTransfer {
+    idxs: Vec<usize>,
+    wallet_pubkeys: Vec<Pubkey>,
+    tx: VersionedTransaction,
+    last_valid_block_height: u64,
+    durable_nonce: Option<(Pubkey, Hash)>,
+    compute_unit_price: u64,
+}
+
+// acquires a nonce (or blockhash), prices, simulates and signs the tx of given batch
+fn prepare_transfer(
+    rpc_client: &RpcClient,
+    payer: &dyn Signer,
+    TransferJob {
+        idxs,
+        wallet_pubkeys,
+        transfer_ixs,
+        previous_compute_unit_price,
+    }: TransferJob,
+    nonce_pool: Option<&Mutex<NoncePool>>,
+    blockhash_cache: &Mutex<BlockhashCache>,
+    epoch_cache: &Mutex<EpochCache>,
+    options: &SendOptions,
+) -> Result<PreparedTransfer> {
+    if is_interrupted() {
+        return Err(Error::Interrupted);
+    }
+    // NOTE: transfer_checked_with_fee fails unless its fee is the fee of the epoch the tx lands in
+    if let Some(transfer_fee_change_epoch) = options.transfer_fee_change_epoch {
+        if epoch_cache.lock().unwrap().get(rpc_client)? >= transfer_fee_change_epoch {
+            return Err(Error::TransferFeeChanged(transfer_fee_change_epoch));
+        }
+    }
+    // NOTE: a nonce account backs a single tx at a time, so this waits for one to free up
+    let durable_nonce = match nonce_pool {
+        Some(nonce_pool) => match acquire_nonce_account(rpc_client, nonce_pool)? {
+            Some(durable_nonce) => Some(durable_nonce),
+            None if is_interrupted() => return Err(Error::Interrupted),
+            None => return Err(Error::NonceError("No nonce account freed up".to_string())),
+        },
+        None => None,
+    };
+    let (blockhash, last_valid_block_height) = match durable_nonce {
+        // NOTE: durable nonce txs do not expire by block height
+        Some((_, nonce)) => (nonce, u64::MAX),
+        None => blockhash_cache.lock().unwrap().get(rpc_client)?,
+    };
+    let compute_unit_price = match options.priority_fee_policy.as_ref() {
+        Some(priority_fee_policy) => {
+            let compute_unit_price = priority_fee_policy.compute_unit_price(
+                rpc_client,
+                &[payer.pubkey(), options.source_ata],
+                &transfer_ixs,
+                previous_compute_unit_price,
+            );
+            log::debug!("Setting compute unit price to {compute_unit_price}");
+            compute_unit_price
+        }
+        None => options.compute_unit_price,
+    };
+    let prep_tx_with_compute_unit_limit = |compute_unit_limit| {
+        // NOTE: advance nonce ix has to be the first ix
+        let ixs: Vec<Instruction> = durable_nonce
+            .map(|(nonce_pubkey, _)| advance_nonce_account(&nonce_pubkey, &payer.pubkey()))
+            .into_iter()
+            .chain(get_compute_budget_ixs(
+                compute_unit_limit,
+                compute_unit_price,
+            ))
+            .chain(transfer_ixs.iter().cloned())
+            .collect();
+        prep_tx_with_blockhash(
+            payer,
+            &ixs,
+            options.address_lookup_table_accounts,
+            blockhash,
+        )
+    };
+
+    let compute_unit_limit = options.compute_unit_limit;
+    let mut tx = prep_tx_with_compute_unit_limit(compute_unit_limit)?;
+    if let Some(compute_unit_margin_percent) = options.compute_unit_margin_percent {
+        match simulate_compute_unit_limit(rpc_client, &tx, compute_unit_margin_percent) {
+            Some(simulated_compute_unit_limit) => {
+                log::debug!("Setting compute unit limit to {simulated_compute_unit_limit}");
+                tx = prep_tx_with_compute_unit_limit(
+                    simulated_compute_unit_limit.min(compute_unit_limit),
+                )?;
+            }
+            None => log::warn!(
+                "Simulation failed, falling back to compute unit limit {compute_unit_limit}"
+            ),
+        }
+    }
+    Ok(PreparedTransfer {
+        idxs,
+        wallet_pubkeys,
+        tx,
+        last_valid_block_height,
+        durable_nonce,
+        compute_unit_price,
+    })
+}
+
 #[derive(Debug)]
 pub struct WalletList(pub Vec<WalletListEntry>);
 
@@ -221,24 +749,39 @@ impl CsvListSerde for WalletList {
     fn save_to_path(&mut self, path: &PathBuf) -> Result<()> {
         log::info!("Saving status data to {path:?} ...");
         log::info!("{:#?}", self.count_each_status());
-        create_backup_if_file_exists(path)?;
-        let mut wtr = csv::Writer::from_path(path)?;
+        // NOTE: the sent stage is rewritten in place while the send journal points at its entries,
+        //  so it is replaced atomically instead of being truncated and rewritten
+        copy_backup_if_file_exists(path)?;
+        let mut wtr = csv::Writer::from_writer(vec![]);
         self.0.sort_by(|a, b| a.wallet_pubkey.cmp(&b.wallet_pubkey));
         for entry in self.0.iter() {
             wtr.write_record(entry.to_record())?;
         }
-        wtr.flush()?;
+        let data = wtr
+            .into_inner()
+            .map_err(|err| Error::IoError(err.into_error()))?;
+        write_file_atomically(path, &data)?;
         log::info!("Finished saving status data");
         Ok(())
     }
 }
 
 impl WalletList {
+    pub fn find_entry(&self, wallet_pubkey: &Pubkey) -> Option<&WalletListEntry> {
+        self.0
+            .binary_search_by(|entry| entry.wallet_pubkey.cmp(wallet_pubkey))
+            .ok()
+            .map(|idx| &self.0[idx])
+    }
+
+    // NOTE: disqualified entries are counted by reason
     pub fn count_each_status(&self) -> HashMap<String, usize> {
         self.0.iter().fold(HashMap::new(), |mut map, entry| {
-            map.entry(entry.status.to_string())
-                .and_modify(|count| *count += 1)
-                .or_insert(1);
+            let key = match &entry.status {
+                Status::Disqualified(reason) => format!("{}({reason})", entry.status),
+                status => status.to_string(),
+            };
+            map.entry(key).and_modify(|count| *count += 1).or_insert(1);
             map
         })
     }
@@ -251,6 +794,38 @@ impl WalletList {
             .len()
     }
 
+    // qualified entries whose ata will be created on send
+    pub fn count_atas_to_create(&self, payer_pubkey: &Pubkey) -> usize {
+        self.0
+            .iter()
+            .filter(|entry| {
+                matches!(entry.status, Status::Qualified)
+                    && matches!(entry.ata_kind, Some(AtaKind::Create))
+                    && entry.wallet_pubkey != *payer_pubkey
+            })
+            .count()
+    }
+
+    // returns (total amount to transfer, total fee withheld) over entries of given status
+    pub fn sum_transfer_amount_and_fee(
+        &self,
+        payer_pubkey: &Pubkey,
+        transfer_fee: Option<&TransferFee>,
+        gross_up_transfer_fee: bool,
+        filter: impl Fn(usize, &Status) -> bool,
+    ) -> (u64, u64) {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| {
+                filter(*idx, &entry.status) && entry.wallet_pubkey != *payer_pubkey
+            })
+            .map(|(_idx, entry)| entry.transfer_amount_and_fee(transfer_fee, gross_up_transfer_fee))
+            .fold((0, 0), |(total_amount, total_fee), (amount, fee)| {
+                (total_amount + amount, total_fee + fee)
+            })
+    }
+
     pub fn count_failed(&self) -> usize {
         self.0
             .iter()
@@ -284,6 +859,16 @@ impl WalletList {
         }
     }
 
+    // Expired -> Qualified
+    // used for retrying transfer_airdrop procedure
+    pub fn set_expired_to_qualified(&mut self) {
+        for entry in self.0.iter_mut() {
+            if let Status::Expired(_) = entry.status {
+                entry.status = Status::Qualified;
+            }
+        }
+    }
+
     // Failed -> Excluded
     // used for excluding failed entries
     pub fn set_failed_to_excluded(&mut self) {
@@ -293,14 +878,33 @@ impl WalletList {
     }
 
     // Unprocessed -> Qualified | Disqualified | Failed
+    // returns the number of qualified entries for each token account extension type
+    #[allow(clippy::too_many_arguments)]
     pub fn check_unprocessed(
         &mut self,
         rpc_client: &RpcClient,
         token_mint_pubkey: &Pubkey,
         token_program_id: &Pubkey,
         token_decimals: u8,
-    ) {
+        mint_extensions: &MintExtensions,
+        rules: &QualificationRules,
+        create_missing_atas: bool,
+        discover_token_accounts: bool,
+    ) -> HashMap<String, usize> {
         log::debug!("Checking qualification ...");
+        let mut extension_counts = HashMap::new();
+        if mint_extensions.non_transferable {
+            log::warn!("Mint is non-transferable, disqualifying every entry");
+            for entry in self
+                .0
+                .iter_mut()
+                .filter(|entry| matches!(entry.status, Status::Unprocessed))
+            {
+                entry.status = Status::Disqualified(DisqualificationReason::NonTransferable);
+            }
+            return extension_counts;
+        }
+
         for entries in self
             .0
             .iter_mut()
@@ -316,91 +920,430 @@ impl WalletList {
                     entry.ata.unwrap()
                 })
                 .collect();
-            let statuses = check_atas(rpc_client, &atas, token_decimals);
-            for (entry, status) in entries.iter_mut().zip(statuses) {
-                entry.status = status;
+            let wallets: Vec<Pubkey> = entries.iter().map(|entry| entry.wallet_pubkey).collect();
+            // NOTE: only derived atas can be created or replaced by a discovered token account,
+            //       an ata given by the wallet list has to exist
+            let is_derived: Vec<bool> = entries
+                .iter()
+                .map(|entry| matches!(entry.ata_kind, Some(AtaKind::Derived | AtaKind::Create)))
+                .collect();
+            let can_create_atas: Vec<bool> = is_derived
+                .iter()
+                .map(|is_derived| create_missing_atas && *is_derived)
+                .collect();
+            let ata_checks = check_atas(
+                rpc_client,
+                &wallets,
+                &atas,
+                &can_create_atas,
+                token_mint_pubkey,
+                token_program_id,
+                token_decimals,
+                rules,
+            );
+
+            let mut lookup_idxs = Vec::new();
+            for (idx, ((entry, ata_check), is_derived)) in entries
+                .iter_mut()
+                .zip(ata_checks)
+                .zip(is_derived)
+                .enumerate()
+            {
+                let exists = ata_check.exists;
+                entry.apply_ata_check(ata_check, &mut extension_counts);
+                if matches!(entry.status, Status::Qualified) && is_derived {
+                    if exists {
+                        entry.ata_kind = Some(AtaKind::Derived);
+                    } else if mint_extensions.default_frozen {
+                        entry.status = Status::Disqualified(DisqualificationReason::DefaultFrozen);
+                    } else {
+                        entry.ata_kind = Some(AtaKind::Create);
+                    }
+                }
+
+                if discover_token_accounts
+                    && is_derived
+                    && !exists
+                    && !matches!(entry.status, Status::Failed(_))
+                {
+                    lookup_idxs.push(idx);
+                }
+            }
+
+            let lookup_wallets: Vec<Pubkey> = lookup_idxs
+                .iter()
+                .map(|idx| entries[*idx].wallet_pubkey)
+                .collect();
+            let mut discovered_idxs = Vec::new();
+            for (idx, res) in lookup_idxs.into_iter().zip(find_largest_token_accounts(
+                rpc_client,
+                &lookup_wallets,
+                token_mint_pubkey,
+            )) {
+                let entry = &mut entries[idx];
+                match res {
+                    Ok(Some(token_account)) => {
+                        log::debug!("Discovered {token_account} for {}", entry.wallet_pubkey);
+                        entry.ata = Some(token_account);
+                        entry.ata_kind = Some(AtaKind::Discovered);
+                        discovered_idxs.push(idx);
+                    }
+                    Ok(None) => {}
+                    Err(err) => entry.status = Status::Failed(err.to_string()),
+                }
+            }
+
+            // discovered token accounts have to pass the same qualification rules
+            if !discovered_idxs.is_empty() {
+                let wallets: Vec<Pubkey> = discovered_idxs
+                    .iter()
+                    .map(|idx| entries[*idx].wallet_pubkey)
+                    .collect();
+                // UNWRAP-SAFETY: ata of a discovered entry is always set
+                let atas: Vec<Pubkey> = discovered_idxs
+                    .iter()
+                    .map(|idx| entries[*idx].ata.unwrap())
+                    .collect();
+                let ata_checks = check_atas(
+                    rpc_client,
+                    &wallets,
+                    &atas,
+                    &vec![false; atas.len()],
+                    token_mint_pubkey,
+                    token_program_id,
+                    token_decimals,
+                    rules,
+                );
+                for (idx, ata_check) in discovered_idxs.iter().zip(ata_checks) {
+                    entries[*idx].apply_ata_check(ata_check, &mut extension_counts);
+                }
             }
         }
+        extension_counts
     }
 
-    // Qualified -> Succeeded | Failed
-    // NOTE: Failed status might contain false positive (rpc returned failure but token transfer happened)
+    // Qualified -> Qualified | Failed
+    // builds the (idx, ixs) of every qualified entry, to be packed into batches
     #[allow(clippy::too_many_arguments)]
-    pub fn transfer_airdrop(
+    pub fn build_transfer_ixs(
         &mut self,
-        rpc_client: &RpcClient,
         token_mint_pubkey: &Pubkey,
         token_program_id: &Pubkey,
         token_decimals: u8,
         source_ata: &Pubkey,
         payer: &dyn Signer,
-        compute_unit_limit: u32,
-        compute_unit_price: u64,
-        dry_run: bool,
-        should_confirm: bool,
-    ) {
-        let transfer_ixs_with_idx: Vec<(usize, Instruction)> = self
+        transfer_fee: Option<&TransferFee>,
+        gross_up_transfer_fee: bool,
+        transfer_hook_resolver: Option<&TransferHookResolver>,
+    ) -> TransferBatch {
+        // NOTE: enumerated before filtering out the payer, so that idxs are positions in self.0
+        let ixs_with_idx: Vec<(_, Result<Vec<Instruction>>)> = self
             .0
             .iter()
-            .filter(|entry| entry.wallet_pubkey != payer.pubkey())
             .enumerate()
+            .filter(|(_, entry)| entry.wallet_pubkey != payer.pubkey())
             .filter_map(|(idx, entry)| match entry.status {
                 Status::Qualified => {
-                    let ix = entry.to_transfer_ix(
+                    let transfer_ix = match entry.to_transfer_ix(
                         token_mint_pubkey,
                         token_program_id,
                         token_decimals,
                         source_ata,
                         payer,
-                    );
-                    Some((idx, ix))
+                        transfer_fee,
+                        gross_up_transfer_fee,
+                        transfer_hook_resolver,
+                    ) {
+                        Ok(transfer_ix) => transfer_ix,
+                        Err(err) => return Some(((idx, entry.wallet_pubkey), Err(err))),
+                    };
+                    let mut ixs = Vec::new();
+                    if matches!(entry.ata_kind, Some(AtaKind::Create)) {
+                        ixs.push(entry.to_create_ata_ix(
+                            token_mint_pubkey,
+                            token_program_id,
+                            payer,
+                        ));
+                    }
+                    // NOTE: memo has to be the ix right before the transfer
+                    if entry.memo_required {
+                        ixs.push(build_memo(AIRDROP_MEMO.as_bytes(), &[]));
+                    }
+                    ixs.push(transfer_ix);
+                    Some(((idx, entry.wallet_pubkey), Ok(ixs)))
                 }
                 _ => None,
             })
             .collect();
 
-        log::info!(
-            "Sending {} txs ...",
-            transfer_ixs_with_idx
-                .len()
-                .div_ceil(TRANSFER_IXS_CHUNK_SIZE)
-        );
-        let compute_budget_ixs = get_compute_budget_ixs(compute_unit_limit, compute_unit_price);
-        for ixs_with_idx in transfer_ixs_with_idx.chunks(TRANSFER_IXS_CHUNK_SIZE) {
-            let (idxs, transfer_ixs): (Vec<_>, Vec<_>) = ixs_with_idx.iter().cloned().unzip();
+        let mut entries_ixs_with_idx = Vec::new();
+        for ((idx, wallet_pubkey), ixs) in ixs_with_idx {
+            match ixs {
+                Ok(ixs) => entries_ixs_with_idx.push(((idx, wallet_pubkey), ixs)),
+                Err(err) => {
+                    log::error!("Could not build transfer ix: {err}");
+                    self.0.get_mut(idx).unwrap().status = Status::Failed(err.to_string());
+                }
+            }
+        }
 
-            let ixs: Vec<Instruction> = compute_budget_ixs
-                .iter()
-                .cloned()
-                .chain(transfer_ixs)
-                .collect();
+        entries_ixs_with_idx
+    }
 
-            // TODO: error handling and retry
-            let tx = prep_tx(rpc_client, payer, &ixs).unwrap();
+    // Qualified -> Unconfirmed
+    // NOTE: txs that the rpc failed to send are kept unconfirmed too since they might still land,
+    // the signed txs are recorded in sent_tx_list so that they are rebroadcast as is until they expire
+    // NOTE: txs are prepared (nonce, price, simulation, signing) by pipeline.concurrency workers, recorded
+    // on this thread, and then submitted by another pipeline.concurrency workers
+    // returns whether sending stopped because the transfer fee changed, in which case the remaining
+    // (still qualified) entries have to be rebuilt with the fee of the new epoch
+    pub fn transfer_airdrop(
+        &mut self,
+        rpc_client: &RpcClient,
+        payer: &(dyn Signer + Sync),
+        SendRecords {
+            sent_tx_list,
+            mut nonce_account_list,
+            mut send_journal,
+        }: SendRecords,
+        batches: Vec<TransferBatch>,
+        options: &SendOptions,
+    ) -> bool {
+        log::info!("Sending {} txs ...", batches.len());
+        let pipeline = &options.pipeline;
+        // NOTE: entries retried from a previous send round escalate from their last price
+        let jobs: Vec<TransferJob> = batches
+            .into_iter()
+            .map(|batch| {
+                let (entries, transfer_ixs): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+                let (idxs, wallet_pubkeys): (Vec<usize>, Vec<Pubkey>) = entries.into_iter().unzip();
+                let previous_compute_unit_price = idxs
+                    .iter()
+                    .filter_map(|idx| self.0[*idx].compute_unit_price)
+                    .max();
+                TransferJob {
+                    idxs,
+                    wallet_pubkeys,
+                    transfer_ixs: transfer_ixs.into_iter().flatten().collect(),
+                    previous_compute_unit_price,
+                }
+            })
+            .collect();
+        let jobs = Mutex::new(jobs.into_iter());
+        let nonce_pool = match nonce_account_list.as_deref() {
+            Some(nonce_account_list) => match NoncePool::new(nonce_account_list) {
+                Ok(nonce_pool) => Some(Mutex::new(nonce_pool)),
+                Err(err) => {
+                    log::error!("Failed to read nonce accounts, stopping (remaining entries stay qualified): {err}");
+                    return false;
+                }
+            },
+            None => None,
+        };
+        let blockhash_cache = Mutex::new(BlockhashCache::new(pipeline.blockhash_refresh_interval));
+        let epoch_cache = Mutex::new(EpochCache::new(pipeline.blockhash_refresh_interval));
+        let transfer_fee_changed = AtomicBool::new(false);
+        // set once a worker fails (or is interrupted), so that no more jobs are picked up
+        let stopped = AtomicBool::new(false);
+        let min_dispatch_interval = pipeline
+            .max_tps
+            .map(|max_tps| Duration::from_secs_f64(1.0 / max_tps as f64));
+        let consecutive_send_errors = AtomicU32::new(0);
+        let (prepared_sender, prepared_receiver) =
+            std::sync::mpsc::sync_channel::<PreparedTransfer>(pipeline.concurrency);
+        let (tx_sender, tx_receiver) =
+            std::sync::mpsc::sync_channel::<VersionedTransaction>(pipeline.concurrency);
+        let tx_receiver = Mutex::new(tx_receiver);
+        std::thread::scope(|scope| {
+            for _ in 0..pipeline.concurrency {
+                let prepared_sender = prepared_sender.clone();
+                let (jobs, nonce_pool, blockhash_cache, epoch_cache) =
+                    (&jobs, nonce_pool.as_ref(), &blockhash_cache, &epoch_cache);
+                let (stopped, transfer_fee_changed) = (&stopped, &transfer_fee_changed);
+                scope.spawn(move || loop {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Some(job) = jobs.lock().unwrap().next() else {
+                        break;
+                    };
+                    match prepare_transfer(
+                        rpc_client,
+                        payer,
+                        job,
+                        nonce_pool,
+                        blockhash_cache,
+                        epoch_cache,
+                        options,
+                    ) {
+                        Ok(prepared) if options.dry_run => {
+                            match rpc_client.simulate_transaction(&prepared.tx) {
+                                Ok(res) => log::info!("{res:#?}"),
+                                Err(err) => {
+                                    if !stopped.swap(true, Ordering::Relaxed) {
+                                        log::error!("Failed to simulate tx, stopping: {err}");
+                                    }
+                                    break;
+                                }
+                            }
+                        }
+                        // NOTE: blocks while the recording thread is busy
+                        Ok(prepared) => prepared_sender.send(prepared).unwrap(),
+                        Err(Error::Interrupted) => {
+                            if !stopped.swap(true, Ordering::Relaxed) {
+                                log::warn!("Interrupted, stopping (remaining entries stay qualified)");
+                            }
+                            break;
+                        }
+                        Err(Error::TransferFeeChanged(epoch)) => {
+                            if !stopped.swap(true, Ordering::Relaxed) {
+                                log::info!("Transfer fee changes in epoch {epoch}, stopping to rebuild the remaining transfers");
+                            }
+                            transfer_fee_changed.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                        Err(err) => {
+                            if !stopped.swap(true, Ordering::Relaxed) {
+                                log::error!("Failed to prepare tx, stopping (remaining entries stay qualified): {err}");
+                            }
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(prepared_sender);
+            for _ in 0..pipeline.concurrency {
+                scope.spawn(|| loop {
+                    let Ok(tx) = tx_receiver.lock().unwrap().recv() else {
+                        break;
+                    };
+                    submit_tx(
+                        rpc_client,
+                        &tx,
+                        options.should_confirm,
+                        &consecutive_send_errors,
+                    );
+                });
+            }
 
-            if dry_run {
-                log::info!("{:#?}", rpc_client.simulate_transaction(&tx).unwrap());
-            } else {
-                let status = if should_confirm {
-                    let _tx_res = rpc_client
-                        .send_and_confirm_transaction_with_spinner_and_commitment(
-                            &tx,
-                            rpc_client.commitment(),
-                        );
-                    // NOTE: just set it to unconfirmed to be safe (always manually run the confirm stage to resolve)
-                    Status::Unconfirmed(tx.get_signature().to_owned())
-                } else {
-                    let tx_res = rpc_client.send_transaction(&tx);
-                    match tx_res {
-                        Ok(sig) => Status::Unconfirmed(sig),
-                        Err(err) => Status::Failed(err.to_string()),
+            let mut last_dispatch: Option<Instant> = None;
+            let mut journal_failed = false;
+            // NOTE: always drained so that no prepare worker stays blocked, txs that are not recorded are
+            // dropped unsent (their entries stay qualified)
+            for PreparedTransfer {
+                idxs,
+                wallet_pubkeys,
+                tx,
+                last_valid_block_height,
+                durable_nonce,
+                compute_unit_price,
+            } in prepared_receiver
+            {
+                // NOTE: in-flight txs are still submitted by the workers before the scope returns
+                if is_interrupted() {
+                    if !stopped.swap(true, Ordering::Relaxed) {
+                        log::warn!("Interrupted, stopping (remaining entries stay qualified)");
                     }
-                };
+                    continue;
+                }
+                if journal_failed {
+                    continue;
+                }
+                // NOTE: recorded before sending so that the tx can be rebroadcast whatever the rpc returns
+                let sig = tx.get_signature().to_owned();
+                let sent_tx = SentTx::new(
+                    &tx,
+                    last_valid_block_height,
+                    durable_nonce.as_ref().map(|(nonce_pubkey, _)| nonce_pubkey),
+                );
+                if let Some(send_journal) = send_journal.as_deref_mut() {
+                    let record = SendJournalRecord {
+                        idxs: idxs.clone(),
+                        // NOTE: taken from the entries the ixs were built for, not looked up by idx
+                        wallet_pubkeys: wallet_pubkeys
+                            .iter()
+                            .map(|wallet_pubkey| wallet_pubkey.to_string())
+                            .collect(),
+                        signature: sig.to_string(),
+                        sent_tx: sent_tx.clone(),
+                        compute_unit_price,
+                    };
+                    if let Err(err) = send_journal.append(&record) {
+                        journal_failed = true;
+                        stopped.store(true, Ordering::Relaxed);
+                        log::error!("Failed to write send journal, stopping (remaining entries stay qualified): {err}");
+                        continue;
+                    }
+                }
+                sent_tx_list.insert(&sig, sent_tx);
+                if let Some((nonce_pubkey, nonce)) = durable_nonce.as_ref() {
+                    if let Some(nonce_account_list) = nonce_account_list.as_deref_mut() {
+                        nonce_account_list.set_last_used_nonce(nonce_pubkey, nonce);
+                    }
+                    if let Some(nonce_pool) = nonce_pool.as_ref() {
+                        nonce_pool
+                            .lock()
+                            .unwrap()
+                            .set_last_used_nonce(nonce_pubkey, nonce);
+                    }
+                }
+                // NOTE: just set it to unconfirmed to be safe (always manually run the confirm stage to resolve)
+                let status = Status::Unconfirmed(sig);
                 for idx in idxs {
-                    self.0.get_mut(idx).unwrap().status = status.clone();
+                    let entry = self.0.get_mut(idx).unwrap();
+                    entry.status = status.clone();
+                    entry.compute_unit_price = Some(compute_unit_price);
                 }
+
+                // backpressure: slow down while the rpc keeps failing, on top of the tps limit
+                let n_errors = consecutive_send_errors.load(Ordering::Relaxed);
+                if n_errors != 0 {
+                    let backoff = get_send_backoff(n_errors);
+                    log::debug!("{n_errors} consecutive send errors, backing off {backoff:?}");
+                    std::thread::sleep(backoff);
+                }
+                if let (Some(min_dispatch_interval), Some(last_dispatch)) =
+                    (min_dispatch_interval, last_dispatch)
+                {
+                    std::thread::sleep(
+                        min_dispatch_interval.saturating_sub(last_dispatch.elapsed()),
+                    );
+                }
+                last_dispatch = Some(Instant::now());
+                // NOTE: blocks while every submit worker is busy
+                tx_sender.send(tx).unwrap();
             }
+            drop(tx_sender);
+        });
+        transfer_fee_changed.into_inner()
+    }
+
+    // Qualified -> Unconfirmed, for the entries of a batch submitted by an interrupted send
+    pub fn apply_send_journal_record(
+        &mut self,
+        SendJournalRecord {
+            idxs,
+            wallet_pubkeys,
+            signature,
+            compute_unit_price,
+            ..
+        }: &SendJournalRecord,
+    ) -> Result<Signature> {
+        let sig = Signature::from_str(signature)?;
+        for (idx, wallet_pubkey) in idxs.iter().zip(wallet_pubkeys) {
+            let entry = self
+                .0
+                .get_mut(*idx)
+                .filter(|entry| entry.wallet_pubkey.to_string() == *wallet_pubkey)
+                .ok_or_else(|| {
+                    Error::SendJournalError(format!(
+                        "{sig}: Entry {idx} is not {wallet_pubkey} in the sent stage"
+                    ))
+                })?;
+            entry.status = Status::Unconfirmed(sig);
+            entry.compute_unit_price = Some(*compute_unit_price);
         }
+        Ok(sig)
     }
 
     pub fn get_unconfirmed_sigs(&self) -> HashSet<Signature> {
@@ -414,15 +1357,26 @@ impl WalletList {
             .collect()
     }
 
-    // Unconfirmed -> Succeeded | Unconfirmed
-    // returns number of unconfirmed sigs
-    pub fn confirm(&mut self, rpc_client: &RpcClient) -> usize {
+    // Unconfirmed -> Succeeded | Failed | Expired | Unconfirmed (pending)
+    // returns number of pending sigs
+    pub fn confirm(&mut self, rpc_client: &RpcClient, sent_tx_list: &SentTxList) -> usize {
         let unconfirmed_signatures = self.get_unconfirmed_sigs();
+        // NOTE: fetched before the statuses, so that a tx past this height without a status provably never landed
+        let block_height = rpc_client
+            .get_block_height_with_commitment(CommitmentConfig::finalized())
+            .inspect_err(|err| log::warn!("Failed to get block height: {err}"))
+            .ok();
 
         let unconfirmed_count = unconfirmed_signatures.len();
         log::debug!("Confirming {} txs ...", unconfirmed_count);
         let mut confirmed_count: usize = 0;
+        let mut failed_count: usize = 0;
+        let mut expired_count: usize = 0;
         for sig in unconfirmed_signatures {
+            if is_interrupted() {
+                log::warn!("Interrupted, remaining txs stay unconfirmed");
+                break;
+            }
             let res = confirm_signature(rpc_client, &sig);
             log::trace!("{res:?}");
             if let Ok(val) = res {
@@ -447,28 +1401,220 @@ impl WalletList {
                                 _ => false,
                             })
                             .for_each(|entry| entry.set_unconfirmed_to_failed());
+                        failed_count += 1;
                     }
                     None => {
-                        log::debug!("Unconfirmed: {sig:?}");
+                        let expired = match sent_tx_list.durable_nonce(&sig) {
+                            // NOTE: the status is looked up again after the (finalized) nonce, so that
+                            //  the tx itself advancing the nonce is not mistaken for expiry
+                            Ok(Some((nonce_pubkey, nonce))) => is_durable_nonce_expired(
+                                &nonce,
+                                get_durable_nonce(
+                                    rpc_client,
+                                    &nonce_pubkey,
+                                    CommitmentConfig::finalized(),
+                                )
+                                .ok(),
+                                || matches!(confirm_signature(rpc_client, &sig), Ok(None)),
+                            ),
+                            _ => is_blockhash_expired(
+                                block_height,
+                                sent_tx_list.last_valid_block_height(&sig),
+                            ),
+                        };
+                        match expired {
+                            Some(true) => {
+                                log::debug!("Expired: {sig:?}");
+                                self.0
+                                    .iter_mut()
+                                    .filter(|entry| match entry.status {
+                                        Status::Unconfirmed(signature) => signature == sig,
+                                        _ => false,
+                                    })
+                                    .for_each(|entry| entry.set_unconfirmed_to_expired());
+                                expired_count += 1;
+                            }
+                            Some(false) => {
+                                log::debug!("Pending: {sig:?}");
+                            }
+                            None => {
+                                log::debug!("Unconfirmed (validity window unknown): {sig:?}");
+                            }
+                        }
                     }
                 }
             } else {
                 log::debug!("Failed to get tx: {sig:?}");
             }
         }
-        let unconfirmed_count = unconfirmed_count - confirmed_count;
+        let pending_count = unconfirmed_count - confirmed_count - failed_count - expired_count;
         log::debug!(
-            "Confirmed: {}; Unconfirmed: {}",
+            "Confirmed: {}; Failed: {}; Expired: {}; Pending: {}",
             confirmed_count,
-            unconfirmed_count
+            failed_count,
+            expired_count,
+            pending_count
         );
-        unconfirmed_count
+        pending_count
     }
 
-    // Unconfirmed -> Failed
-    pub fn set_unconfirmed_to_failed(&mut self) {
-        for entry in self.0.iter_mut() {
-            entry.set_unconfirmed_to_failed();
+    // Unconfirmed -> Unconfirmed (rebroadcast)
+    // returns number of rebroadcast txs
+    pub fn rebroadcast_unconfirmed(
+        &self,
+        rpc_client: &RpcClient,
+        sent_tx_list: &SentTxList,
+        dry_run: bool,
+    ) -> Result<usize> {
+        let mut rebroadcast_count: usize = 0;
+        for sig in self.get_unconfirmed_sigs() {
+            if is_interrupted() {
+                log::warn!("Interrupted, stopping rebroadcast");
+                break;
+            }
+            let Some((tx, _)) = sent_tx_list.get(&sig)? else {
+                log::warn!("Could not find sent tx {sig}, skipping rebroadcast");
+                continue;
+            };
+            log::debug!("Rebroadcasting: {sig:?}");
+            if !dry_run {
+                rpc_client
+                    .send_transaction_with_config(
+                        &tx,
+                        RpcSendTransactionConfig {
+                            skip_preflight: true,
+                            ..Default::default()
+                        },
+                    )
+                    .inspect_err(|err| log::warn!("Failed to rebroadcast {sig}: {err}"))
+                    .ok();
+            }
+            rebroadcast_count += 1;
+        }
+        Ok(rebroadcast_count)
+    }
+}
+
+// whether a blockhash tx without a status can no longer land (None if unknown):
+// once the finalized block height is past its last valid block height
+fn is_blockhash_expired(
+    finalized_block_height: Option<u64>,
+    last_valid_block_height: Option<u64>,
+) -> Option<bool> {
+    finalized_block_height
+        .zip(last_valid_block_height)
+        .map(|(block_height, last_valid_block_height)| block_height > last_valid_block_height)
+}
+
+// whether a durable nonce tx without a status can no longer land (None if unknown):
+// a durable nonce tx stays valid until its nonce advances, and expired then unless it landed meanwhile
+fn is_durable_nonce_expired(
+    nonce: &Hash,
+    finalized_nonce: Option<Hash>,
+    has_no_status: impl FnOnce() -> bool,
+) -> Option<bool> {
+    finalized_nonce.map(|finalized_nonce| finalized_nonce != *nonce && has_no_status())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::SentTx;
+
+    use super::*;
+
+    fn wallet_list(n: u8) -> WalletList {
+        WalletList(
+            (1..=n)
+                .map(|i| WalletListEntry {
+                    wallet_pubkey: Pubkey::new_from_array([i; 32]),
+                    amount_to_airdrop: i as u64,
+                    status: Status::Qualified,
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    fn journal_record(idxs: Vec<usize>, wallet_pubkeys: Vec<Pubkey>) -> SendJournalRecord {
+        SendJournalRecord {
+            idxs,
+            wallet_pubkeys: wallet_pubkeys.iter().map(Pubkey::to_string).collect(),
+            signature: Signature::from([7; 64]).to_string(),
+            sent_tx: SentTx {
+                tx: String::new(),
+                last_valid_block_height: 0,
+                nonce_account: None,
+            },
+            compute_unit_price: 42,
         }
     }
+
+    #[test]
+    fn apply_send_journal_record_marks_entries_unconfirmed() {
+        let mut wallet_list = wallet_list(3);
+        let record = journal_record(
+            vec![0, 2],
+            vec![
+                Pubkey::new_from_array([1; 32]),
+                Pubkey::new_from_array([3; 32]),
+            ],
+        );
+        let sig = wallet_list.apply_send_journal_record(&record).unwrap();
+        assert_eq!(sig, Signature::from([7; 64]));
+        for idx in [0, 2] {
+            assert!(matches!(wallet_list.0[idx].status, Status::Unconfirmed(s) if s == sig));
+            assert_eq!(wallet_list.0[idx].compute_unit_price, Some(42));
+        }
+        assert!(matches!(wallet_list.0[1].status, Status::Qualified));
+    }
+
+    #[test]
+    fn apply_send_journal_record_rejects_idxs_pointing_at_other_wallets() {
+        let mut wallet_list = wallet_list(3);
+        // e.g. the stage file was edited (and re-sorted) after the journal was written
+        let record = journal_record(vec![1], vec![Pubkey::new_from_array([3; 32])]);
+        assert!(matches!(
+            wallet_list.apply_send_journal_record(&record),
+            Err(Error::SendJournalError(_))
+        ));
+        let record = journal_record(vec![5], vec![Pubkey::new_from_array([3; 32])]);
+        assert!(matches!(
+            wallet_list.apply_send_journal_record(&record),
+            Err(Error::SendJournalError(_))
+        ));
+        assert!(wallet_list
+            .0
+            .iter()
+            .all(|entry| matches!(entry.status, Status::Qualified)));
+    }
+
+    #[test]
+    fn blockhash_txs_expire_past_their_last_valid_block_height() {
+        assert_eq!(is_blockhash_expired(Some(100), Some(100)), Some(false));
+        assert_eq!(is_blockhash_expired(Some(101), Some(100)), Some(true));
+        // durable nonce txs are recorded with u64::MAX
+        assert_eq!(is_blockhash_expired(Some(101), Some(u64::MAX)), Some(false));
+        assert_eq!(is_blockhash_expired(None, Some(100)), None);
+        assert_eq!(is_blockhash_expired(Some(101), None), None);
+    }
+
+    #[test]
+    fn durable_nonce_txs_expire_once_the_nonce_advanced_without_them() {
+        let nonce = Hash::new_from_array([1; 32]);
+        let advanced_nonce = Hash::new_from_array([2; 32]);
+        assert_eq!(
+            is_durable_nonce_expired(&nonce, Some(nonce), || unreachable!()),
+            Some(false)
+        );
+        assert_eq!(
+            is_durable_nonce_expired(&nonce, Some(advanced_nonce), || true),
+            Some(true)
+        );
+        // the tx itself advanced the nonce
+        assert_eq!(
+            is_durable_nonce_expired(&nonce, Some(advanced_nonce), || false),
+            Some(false)
+        );
+        assert_eq!(is_durable_nonce_expired(&nonce, None, || true), None);
+    }
 }
@@ -1,7 +1,10 @@
 use derive_more::Display;
 use solana_program::program_error::ProgramError;
 use solana_rpc_client_api::client_error::Error as RpcError;
-use solana_sdk::{pubkey::ParsePubkeyError, signature::ParseSignatureError, signer::SignerError};
+use solana_sdk::{
+    hash::ParseHashError, pubkey::ParsePubkeyError, signature::ParseSignatureError,
+    signer::SignerError,
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -16,6 +19,30 @@ pub enum Error {
     KeyPairError,
     StageNotReady,
     SignerError(SignerError),
+    JsonError(serde_json::Error),
+    HashError(ParseHashError),
+    HexError(String),
+    BlockNotFound(u64),
+    RaffleVerificationFailed(String),
+    SeedSlotNotInFuture(String),
+    ClaimantNotFound(String),
+    InvalidMerkleProof,
+    AllocationError(String),
+    AtaRentBudgetExceeded(u64),
+    TransferHookError(String),
+    InsufficientTokenBalance(u64),
+    InsufficientSolBalance(u64),
+    ProfileNotFound(String),
+    ClusterMismatch(String),
+    MainnetNotAcknowledged,
+    LookupTableError(String),
+    TxDecodeError(String),
+    NonceError(String),
+    SendJournalError(String),
+    MintPaused(String),
+    TransferFeeChanged(u64),
+    SignalHandlerError(ctrlc::Error),
+    Interrupted,
 }
 
 impl From<std::io::Error> for Error {
@@ -24,6 +51,12 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<ctrlc::Error> for Error {
+    fn from(value: ctrlc::Error) -> Self {
+        Self::SignalHandlerError(value)
+    }
+}
+
 impl From<csv::Error> for Error {
     fn from(value: csv::Error) -> Self {
         Self::CsvError(value)
@@ -60,4 +93,16 @@ impl From<SignerError> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Self::JsonError(value)
+    }
+}
+
+impl From<ParseHashError> for Error {
+    fn from(value: ParseHashError) -> Self {
+        Self::HashError(value)
+    }
+}
+
 impl std::error::Error for Error {}
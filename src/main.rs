@@ -1,7 +1,14 @@
+use std::path::PathBuf;
+
 use clap::{builder::ValueParser, Parser};
 use sanctum_solana_cli_utils::ConfigWrapper;
 
-use crate::{errors::Result, subcmd::Subcmd};
+use crate::{
+    consts::DEFAULT_PROJECT_CONFIG_PATH,
+    data::{Cluster, ClusterProfile, ProjectConfig},
+    errors::Result,
+    subcmd::Subcmd,
+};
 
 mod consts;
 mod data;
@@ -21,6 +28,34 @@ struct Args {
     )]
     pub config: ConfigWrapper,
 
+    #[arg(
+        long,
+        help = "Name of a cluster profile in the project config file, overrides the rpc url of the solana CLI config"
+    )]
+    pub profile: Option<String>,
+
+    #[arg(
+        long,
+        help = "Path to project config file with cluster profiles",
+        default_value = DEFAULT_PROJECT_CONFIG_PATH,
+    )]
+    pub project_config_path: PathBuf,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Cluster the command is intended for, has to match the detected cluster. Required to send or close lookup tables on mainnet-beta"
+    )]
+    pub cluster: Option<Cluster>,
+
+    // resolved from --profile
+    #[arg(skip)]
+    pub cluster_profile: Option<ClusterProfile>,
+
+    // detected from the genesis hash of the rpc endpoint, only when the subcommand needs it
+    #[arg(skip)]
+    pub detected_cluster: Option<Cluster>,
+
     #[arg(
         long,
         short,
@@ -41,6 +76,10 @@ fn main() -> Result<()> {
         .start()
         .unwrap();
 
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if let Some(profile) = args.profile.as_ref() {
+        args.cluster_profile =
+            Some(ProjectConfig::parse_from_path(&args.project_config_path)?.take_profile(profile)?);
+    }
     subcmd::Subcmd::run(args)
 }
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
 use clap::{
     builder::{StringValueParser, TypedValueParser},
@@ -8,10 +8,10 @@ use solana_sdk::pubkey::Pubkey;
 
 use crate::{
     consts::CHECK_MAX_RETRY,
-    data::{CsvListSerde, WalletList},
+    data::{CsvListSerde, QualificationRules, WalletList},
     errors::Result,
     subcmd::Subcmd,
-    utils::{add_to_filename, get_token_mint_info},
+    utils::{add_to_filename, get_mint_extensions, get_rpc_client, get_token_mint_info},
 };
 
 #[derive(Args, Debug)]
@@ -27,6 +27,25 @@ pub struct CheckArgs {
         value_parser = StringValueParser::new().try_map(|s| Pubkey::from_str(&s)),
     )]
     airdrop_token_mint_pubkey: Pubkey,
+
+    #[arg(
+        long,
+        short,
+        help = "Path to qualification rules json file (if not provided, having a token account qualifies)"
+    )]
+    rules_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Qualify wallets without an ata (instead of disqualifying them), so that the ata is created on send"
+    )]
+    create_missing_atas: bool,
+
+    #[arg(
+        long,
+        help = "For wallets without an ata, use their existing token account of the mint with the largest balance (not frozen, owned by the wallet)"
+    )]
+    discover_token_accounts: bool,
 }
 
 impl CheckArgs {
@@ -34,28 +53,47 @@ impl CheckArgs {
         let Self {
             wallet_list_path,
             airdrop_token_mint_pubkey,
+            rules_path,
+            create_missing_atas,
+            discover_token_accounts,
         } = match args.subcmd {
             Subcmd::Check(a) => a,
             _ => unreachable!(),
         };
-        let rpc_client = args.config.rpc_client();
+        let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
         let (token_program_id, token_decimals) =
             get_token_mint_info(&rpc_client, &airdrop_token_mint_pubkey)?;
 
+        let mint_extensions = get_mint_extensions(&rpc_client, &airdrop_token_mint_pubkey)?;
+        log::info!("Mint extensions: {:?}", mint_extensions.extension_types);
+
+        let rules = match rules_path {
+            Some(path) => QualificationRules::parse_from_path(&path)?,
+            None => QualificationRules::default(),
+        };
+
         let mut wallet_list = WalletList::parse_list_from_path(&wallet_list_path)?;
         let wallet_count = wallet_list.0.len();
 
         log::info!("Wallet count: {wallet_count}");
         let stage_save_path = add_to_filename(&wallet_list_path, "checked");
 
+        let mut extension_counts: HashMap<String, usize> = HashMap::new();
         for check_trial_count in 1..=CHECK_MAX_RETRY {
             log::info!("Checking the airdrop qualification ...");
-            wallet_list.check_unprocessed(
+            let trial_extension_counts = wallet_list.check_unprocessed(
                 &rpc_client,
                 &airdrop_token_mint_pubkey,
                 &token_program_id,
                 token_decimals,
+                &mint_extensions,
+                &rules,
+                create_missing_atas,
+                discover_token_accounts,
             );
+            for (extension_type, count) in trial_extension_counts {
+                *extension_counts.entry(extension_type).or_default() += count;
+            }
 
             if !args.dry_run {
                 wallet_list
@@ -86,6 +124,9 @@ impl CheckArgs {
 
         let qualified_wallet_count = wallet_list.count_qualified();
         log::info!("Found {qualified_wallet_count} qualified wallets");
+        // NOTE: MemoTransfer is satisfied on send by adding a memo ix,
+        //       CpiGuard does not affect transfers signed by the payer directly
+        log::info!("Token account extensions of qualified wallets: {extension_counts:#?}");
 
         Ok(())
     }
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{close_lookup_table, deactivate_lookup_table},
+        state::AddressLookupTable,
+    },
+    signature::read_keypair_file,
+    signer::Signer,
+    slot_hashes::MAX_ENTRIES,
+};
+
+use crate::{
+    data::LookupTableList,
+    errors::{Error, Result},
+    subcmd::Subcmd,
+    utils::{get_rpc_client, prep_tx},
+};
+
+#[derive(Args, Debug)]
+#[command(
+    long_about = "Given a sent wallet list, deactivate the lookup tables created by send and close the deactivated ones (reclaiming rent)"
+)]
+pub struct CloseLookupTablesArgs {
+    #[arg(long, short, help = "Path to wallet list csv file")]
+    pub wallet_list_path: PathBuf,
+
+    #[arg(
+        long,
+        short,
+        help = "Path to payer keypair who created the lookup tables"
+    )]
+    payer_path: PathBuf,
+}
+
+impl CloseLookupTablesArgs {
+    pub fn run(args: crate::Args) -> Result<()> {
+        let Self {
+            wallet_list_path,
+            payer_path,
+        } = match args.subcmd {
+            Subcmd::CloseLookupTables(a) => a,
+            _ => unreachable!(),
+        };
+        let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
+        let payer = read_keypair_file(
+            payer_path
+                .to_str()
+                .expect("Could not convert payer_path to str"),
+        )
+        .map_err(|_e| Error::KeyPairError)?;
+
+        let lookup_table_list_path = LookupTableList::path_from_wallet_list_path(&wallet_list_path);
+        if !lookup_table_list_path.try_exists()? {
+            log::info!("No lookup tables to close, terminating");
+            return Ok(());
+        }
+        let lookup_table_list = LookupTableList::parse_from_path(&lookup_table_list_path)?;
+
+        let current_slot = rpc_client.get_slot()?;
+        let mut remaining_lookup_tables = Vec::new();
+        for lookup_table_pubkey in lookup_table_list.pubkeys()? {
+            let Some(account) = rpc_client
+                .get_account_with_commitment(&lookup_table_pubkey, rpc_client.commitment())?
+                .value
+            else {
+                log::info!("Lookup table {lookup_table_pubkey} is already closed");
+                continue;
+            };
+            let deactivation_slot = AddressLookupTable::deserialize(&account.data)
+                .map_err(|err| Error::LookupTableError(err.to_string()))?
+                .meta
+                .deactivation_slot;
+
+            // NOTE: a deactivated table can only be closed once its deactivation slot is no longer in the slot hashes
+            let ix = if deactivation_slot == u64::MAX {
+                log::info!("Deactivating lookup table {lookup_table_pubkey} ...");
+                deactivate_lookup_table(lookup_table_pubkey, payer.pubkey())
+            } else if current_slot > deactivation_slot + MAX_ENTRIES as u64 {
+                log::info!("Closing lookup table {lookup_table_pubkey} ...");
+                close_lookup_table(lookup_table_pubkey, payer.pubkey(), payer.pubkey())
+            } else {
+                log::info!(
+                    "Lookup table {lookup_table_pubkey} can be closed in {} slots",
+                    deactivation_slot + MAX_ENTRIES as u64 - current_slot + 1
+                );
+                remaining_lookup_tables.push(lookup_table_pubkey.to_string());
+                continue;
+            };
+
+            let (tx, _) = prep_tx(&rpc_client, &payer, &[ix], &[])?;
+            if args.dry_run {
+                log::info!("{:#?}", rpc_client.simulate_transaction(&tx)?);
+                remaining_lookup_tables.push(lookup_table_pubkey.to_string());
+                continue;
+            }
+            match rpc_client.send_and_confirm_transaction(&tx) {
+                Ok(sig) if deactivation_slot == u64::MAX => {
+                    log::info!("Deactivated: {sig}");
+                    remaining_lookup_tables.push(lookup_table_pubkey.to_string());
+                }
+                Ok(sig) => log::info!("Closed: {sig}"),
+                Err(err) => {
+                    log::error!("Failed to update lookup table {lookup_table_pubkey}: {err}");
+                    remaining_lookup_tables.push(lookup_table_pubkey.to_string());
+                }
+            }
+        }
+
+        if !args.dry_run {
+            log::info!(
+                "{} lookup tables left to close",
+                remaining_lookup_tables.len()
+            );
+            LookupTableList {
+                lookup_tables: remaining_lookup_tables,
+            }
+            .save_to_path(&lookup_table_list_path)?;
+        }
+
+        Ok(())
+    }
+}
@@ -3,10 +3,10 @@ use std::path::PathBuf;
 use clap::Args;
 
 use crate::{
-    data::{CsvListSerde, WalletList},
+    data::{CsvListSerde, SentTxList, WalletList},
     errors::{Error, Result},
     subcmd::Subcmd,
-    utils::add_to_filename,
+    utils::{add_to_filename, get_rpc_client, is_interrupted, set_interrupt_handler},
 };
 
 #[derive(Args, Debug)]
@@ -22,7 +22,8 @@ impl ConfirmArgs {
             Subcmd::Confirm(a) => a,
             _ => unreachable!(),
         };
-        let rpc_client = args.config.rpc_client();
+        let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
+        set_interrupt_handler()?;
 
         let send_stage_save_path = add_to_filename(&wallet_list_path, "sent");
         let confirm_stage_save_path = add_to_filename(&wallet_list_path, "confirmed");
@@ -37,6 +38,13 @@ impl ConfirmArgs {
         };
 
         let mut wallet_list = WalletList::parse_list_from_path(&base_stage_save_path)?;
+        let sent_tx_list_path = SentTxList::path_from_wallet_list_path(&wallet_list_path);
+        let sent_tx_list = if sent_tx_list_path.try_exists()? {
+            SentTxList::parse_from_path(&sent_tx_list_path)?
+        } else {
+            log::warn!("Could not find sent tx list, unconfirmed txs cannot be marked as expired");
+            SentTxList::default()
+        };
         let total_unconfirmed_count = wallet_list.get_unconfirmed_sigs().len();
         if total_unconfirmed_count == 0 {
             log::info!("No unconfirmed txs, terminating");
@@ -46,11 +54,11 @@ impl ConfirmArgs {
             "Found {} txs to confirm, confirming ...",
             total_unconfirmed_count,
         );
-        let current_unconfirmed_count = wallet_list.confirm(&rpc_client);
+        let pending_count = wallet_list.confirm(&rpc_client, &sent_tx_list);
         log::info!(
-            "Confirmed: {}; Unconfirmed: {}",
-            total_unconfirmed_count - current_unconfirmed_count,
-            current_unconfirmed_count
+            "Resolved (succeeded, failed or expired): {}; Pending: {}",
+            total_unconfirmed_count - pending_count,
+            pending_count
         );
         let stage_save_path = add_to_filename(&wallet_list_path, "confirmed");
 
@@ -59,6 +67,10 @@ impl ConfirmArgs {
                 .save_to_path(&stage_save_path)
                 .unwrap_or_else(|err| log::error!("Failed to save status list: {err:?}"));
         }
+        if is_interrupted() {
+            log::warn!("Confirm was interrupted, run confirm again to resume");
+            return Err(Error::Interrupted);
+        }
 
         Ok(())
     }
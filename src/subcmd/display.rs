@@ -1,6 +1,10 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
-use clap::Args;
+use clap::{
+    builder::{StringValueParser, TypedValueParser},
+    Args,
+};
+use solana_sdk::pubkey::Pubkey;
 
 use crate::{
     data::{CsvListSerde, WalletList},
@@ -17,16 +21,35 @@ pub struct DisplayArgs {
         help = "Path to wallet_list csv file in the format of \"wallet_pubkey,amount_to_airdrop\""
     )]
     pub wallet_list_path: PathBuf,
+
+    #[arg(
+        long,
+        short = 'p',
+        help = "Display the entry (including how its amount was derived) of given wallet pubkey",
+        value_parser = StringValueParser::new().try_map(|s| Pubkey::from_str(&s)),
+    )]
+    wallet_pubkey: Option<Pubkey>,
 }
 
 impl DisplayArgs {
     pub fn run(args: crate::Args) -> Result<()> {
-        let Self { wallet_list_path } = match args.subcmd {
+        let Self {
+            wallet_list_path,
+            wallet_pubkey,
+        } = match args.subcmd {
             Subcmd::Display(a) => a,
             _ => unreachable!(),
         };
         let wallet_list = WalletList::parse_list_from_path(&wallet_list_path)?;
 
+        if let Some(wallet_pubkey) = wallet_pubkey {
+            match wallet_list.find_entry(&wallet_pubkey) {
+                Some(entry) => log::info!("\n{entry}"),
+                None => log::info!("Could not find {wallet_pubkey} in wallet list"),
+            }
+            return Ok(());
+        }
+
         let counts = wallet_list.count_each_status();
         log::info!("{counts:#?}");
 
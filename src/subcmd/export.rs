@@ -0,0 +1,78 @@
+use std::{path::PathBuf, str::FromStr};
+
+use clap::{
+    builder::{StringValueParser, TypedValueParser},
+    Args,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    data::{export_eligibility, CsvListSerde, WalletList},
+    errors::Result,
+    subcmd::Subcmd,
+    utils::{get_rpc_client, get_token_mint_info},
+};
+
+#[derive(Args, Debug)]
+#[command(
+    long_about = "Given a wallet list, export per-wallet allocation json files for static hosting"
+)]
+pub struct ExportArgs {
+    #[arg(long, short, help = "Path to wallet list csv file")]
+    pub wallet_list_path: PathBuf,
+
+    #[arg(
+        long,
+        short,
+        help = "Mint pubkey of the token to be airdropped",
+        value_parser = StringValueParser::new().try_map(|s| Pubkey::from_str(&s)),
+    )]
+    airdrop_token_mint_pubkey: Pubkey,
+
+    #[arg(long, short, help = "Path to directory to export json files to")]
+    output_dir: PathBuf,
+
+    #[arg(
+        long,
+        short,
+        help = "If non zero, group wallets into <prefix>.json files keyed by the first n hex characters of the wallet pubkey bytes instead of one <wallet_pubkey>.json per wallet",
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u64).range(0..=64),
+    )]
+    shard_prefix_length: u64,
+}
+
+impl ExportArgs {
+    pub fn run(args: crate::Args) -> Result<()> {
+        let Self {
+            wallet_list_path,
+            airdrop_token_mint_pubkey,
+            output_dir,
+            shard_prefix_length,
+        } = match args.subcmd {
+            Subcmd::Export(a) => a,
+            _ => unreachable!(),
+        };
+        let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
+        let (_token_program_id, token_decimals) =
+            get_token_mint_info(&rpc_client, &airdrop_token_mint_pubkey)?;
+
+        let wallet_list = WalletList::parse_list_from_path(&wallet_list_path)?;
+        log::info!("Wallet count: {}", wallet_list.0.len());
+
+        if !args.dry_run {
+            log::info!("Exporting eligibility to {output_dir:?} ...");
+            let index = export_eligibility(
+                &output_dir,
+                &airdrop_token_mint_pubkey,
+                token_decimals,
+                &wallet_list.0,
+                shard_prefix_length as usize,
+            )?;
+            log::info!("{index:#?}");
+            log::info!("Finished exporting eligibility");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::{
+    data::{CsvListSerde, MerkleDistributorTree, Status, WalletList},
+    errors::Result,
+    subcmd::Subcmd,
+};
+
+#[derive(Args, Debug)]
+#[command(
+    long_about = "Given a wallet list, generate a merkle tree (root, total and per-wallet proofs) for claim-based distribution"
+)]
+pub struct MerkleArgs {
+    #[arg(long, short, help = "Path to wallet list csv file")]
+    pub wallet_list_path: PathBuf,
+
+    #[arg(long, short, help = "Path to merkle tree json file")]
+    merkle_tree_path: PathBuf,
+}
+
+impl MerkleArgs {
+    pub fn run(args: crate::Args) -> Result<()> {
+        let Self {
+            wallet_list_path,
+            merkle_tree_path,
+        } = match args.subcmd {
+            Subcmd::Merkle(a) => a,
+            _ => unreachable!(),
+        };
+
+        let wallet_list = WalletList::parse_list_from_path(&wallet_list_path)?;
+        // NOTE: only entries that have not been disqualified, excluded or sent are claimable
+        let claims: Vec<_> = wallet_list
+            .0
+            .iter()
+            .filter(|entry| matches!(entry.status, Status::Unprocessed | Status::Qualified))
+            .map(|entry| (entry.wallet_pubkey, entry.amount_to_airdrop))
+            .collect();
+        let skipped_count = wallet_list.0.len() - claims.len();
+        if skipped_count != 0 {
+            log::warn!("Skipping {skipped_count} entries that are not unprocessed nor qualified");
+        }
+
+        log::info!("Building merkle tree of {} claims ...", claims.len());
+        let merkle_tree = MerkleDistributorTree::new(&claims);
+        log::info!("Merkle root: {}", merkle_tree.merkle_root);
+        log::info!("Max num nodes: {}", merkle_tree.max_num_nodes);
+        log::info!("Max total claim: {}", merkle_tree.max_total_claim);
+
+        if !args.dry_run {
+            merkle_tree
+                .save_to_path(&merkle_tree_path)
+                .unwrap_or_else(|err| log::error!("Failed to save merkle tree: {err:?}"));
+        }
+
+        Ok(())
+    }
+}
@@ -1,17 +1,29 @@
 use clap::Subcommand;
 
-use crate::errors::Result;
+use crate::{
+    data::Cluster,
+    errors::{Error, Result},
+    utils::get_rpc_client,
+};
 
 use self::{
-    check::CheckArgs, confirm::ConfirmArgs, display::DisplayArgs, send::SendArgs,
-    snapshot::SnapshotArgs, wallet_list::WalletListArgs,
+    check::CheckArgs, close_lookup_tables::CloseLookupTablesArgs, confirm::ConfirmArgs,
+    display::DisplayArgs, export::ExportArgs, merkle::MerkleArgs, raffle::RaffleArgs,
+    send::SendArgs, snapshot::SnapshotArgs, verify_merkle_proof::VerifyMerkleProofArgs,
+    verify_raffle::VerifyRaffleArgs, wallet_list::WalletListArgs,
 };
 
 mod check;
+mod close_lookup_tables;
 mod confirm;
 mod display;
+mod export;
+mod merkle;
+mod raffle;
 mod send;
 mod snapshot;
+mod verify_merkle_proof;
+mod verify_raffle;
 mod wallet_list;
 
 #[derive(Debug, Subcommand)]
@@ -21,19 +33,65 @@ pub enum Subcmd {
     Check(CheckArgs),
     Send(SendArgs),
     Confirm(ConfirmArgs),
+    CloseLookupTables(CloseLookupTablesArgs),
     Display(DisplayArgs),
+    Raffle(RaffleArgs),
+    VerifyRaffle(VerifyRaffleArgs),
+    Merkle(MerkleArgs),
+    VerifyMerkleProof(VerifyMerkleProofArgs),
+    Export(ExportArgs),
 }
 
 impl Subcmd {
-    pub fn run(args: crate::Args) -> Result<()> {
+    pub fn run(mut args: crate::Args) -> Result<()> {
         log::info!("Running sub command: {:?}", args.subcmd);
+        args.detected_cluster = Self::check_cluster(&args)?;
         match args.subcmd {
             Self::Snapshot(_) => SnapshotArgs::run(args),
             Self::WalletList(_) => WalletListArgs::run(args),
             Self::Check(_) => CheckArgs::run(args),
             Self::Send(_) => SendArgs::run(args),
             Self::Confirm(_) => ConfirmArgs::run(args),
+            Self::CloseLookupTables(_) => CloseLookupTablesArgs::run(args),
             Self::Display(_) => DisplayArgs::run(args),
+            Self::Raffle(_) => RaffleArgs::run(args),
+            Self::VerifyRaffle(_) => VerifyRaffleArgs::run(args),
+            Self::Merkle(_) => MerkleArgs::run(args),
+            Self::VerifyMerkleProof(_) => VerifyMerkleProofArgs::run(args),
+            Self::Export(_) => ExportArgs::run(args),
+        }
+    }
+
+    // subcommands that spend funds or sign payer txs with lasting effects (e.g. closing lookup tables)
+    fn is_destructive(&self) -> bool {
+        matches!(self, Self::Send(_) | Self::CloseLookupTables(_))
+    }
+
+    // NOTE: refuses to run a destructive subcommand on mainnet-beta unless acknowledged with --cluster mainnet-beta.
+    //  The cluster is also detected for dry runs of destructive subcommands so that they can report it
+    fn check_cluster(args: &crate::Args) -> Result<Option<Cluster>> {
+        let is_destructive = args.subcmd.is_destructive() && !args.dry_run;
+        let expected_cluster = args.cluster.or(args
+            .cluster_profile
+            .as_ref()
+            .and_then(|cluster_profile| cluster_profile.cluster));
+        if !args.subcmd.is_destructive() && expected_cluster.is_none() {
+            return Ok(None);
+        }
+
+        let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
+        let cluster = Cluster::from_genesis_hash(&rpc_client.get_genesis_hash()?);
+        log::info!("Detected cluster {cluster} at {}", rpc_client.url());
+        if let Some(expected_cluster) = expected_cluster {
+            if expected_cluster != cluster {
+                log::error!("Expected cluster {expected_cluster}, but detected {cluster}");
+                return Err(Error::ClusterMismatch(cluster.to_string()));
+            }
+        }
+        if is_destructive && cluster == Cluster::MainnetBeta && args.cluster != Some(cluster) {
+            log::error!("Refusing to run on mainnet-beta without --cluster mainnet-beta");
+            return Err(Error::MainnetNotAcknowledged);
         }
+        Ok(Some(cluster))
     }
 }
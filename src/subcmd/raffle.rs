@@ -0,0 +1,161 @@
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
+
+use clap::Args;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{
+    data::{
+        draw_winners, hash_snapshot, CsvListSerde, Provenance, RaffleProof, RaffleSeedSource,
+        Snapshot, WalletList, WalletListEntry, RAFFLE_ALGORITHM,
+    },
+    errors::{Error, Result},
+    subcmd::Subcmd,
+    utils::{from_hex, get_blockhash_at_or_after_slot, get_rpc_client, to_hex},
+};
+
+#[derive(Args, Debug)]
+#[command(
+    long_about = "Given a token snapshot, draw raffle winners weighted by balance and generate a wallet list"
+)]
+pub struct RaffleArgs {
+    #[arg(long, short, help = "Path to wallet list csv file")]
+    pub wallet_list_path: PathBuf,
+
+    #[arg(long, short, help = "Path to token snapshot csv file")]
+    snapshot_path: PathBuf,
+
+    #[arg(long, short = 'o', help = "Path to raffle proof json file")]
+    proof_path: PathBuf,
+
+    #[arg(long, short, help = "The number of winners to draw")]
+    number_of_winners: u64,
+
+    #[arg(
+        long,
+        short,
+        help = "The amount (in token atomic) to airdrop to each winner"
+    )]
+    amount_per_winner: u64,
+
+    #[arg(
+        long,
+        help = "Use the blockhash of the first block at or after this slot as seed, has to be after the current finalized slot",
+        conflicts_with = "seed",
+        required_unless_present = "seed"
+    )]
+    seed_slot: Option<u64>,
+
+    #[arg(long, help = "Use given hex encoded seed")]
+    seed: Option<String>,
+}
+
+impl RaffleArgs {
+    pub fn run(args: crate::Args) -> Result<()> {
+        let Self {
+            wallet_list_path,
+            snapshot_path,
+            proof_path,
+            number_of_winners,
+            amount_per_winner,
+            seed_slot,
+            seed,
+        } = match args.subcmd {
+            Subcmd::Raffle(a) => a,
+            _ => unreachable!(),
+        };
+
+        let snapshot = Snapshot::parse_list_from_path(&snapshot_path)?;
+
+        let (seed, seed_source) = match (seed, seed_slot) {
+            (Some(seed), _) => (from_hex(&seed)?, RaffleSeedSource::Hex),
+            (None, Some(requested_slot)) => {
+                let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
+                // NOTE: a past slot would let the seed (and thus the winners) be picked by trying slots
+                let draw_start_slot =
+                    rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+                if requested_slot <= draw_start_slot {
+                    return Err(Error::SeedSlotNotInFuture(format!(
+                        "requested slot {requested_slot}, current finalized slot {draw_start_slot}"
+                    )));
+                }
+                let (slot, blockhash) =
+                    get_blockhash_at_or_after_slot(&rpc_client, requested_slot)?;
+                log::info!("Using blockhash {blockhash} of slot {slot} as seed");
+                (
+                    blockhash.to_bytes().to_vec(),
+                    RaffleSeedSource::Blockhash {
+                        draw_start_slot,
+                        requested_slot,
+                        slot,
+                        blockhash: blockhash.to_string(),
+                    },
+                )
+            }
+            (None, None) => unreachable!(),
+        };
+
+        log::info!("Drawing {number_of_winners} winners ...");
+        let draws = draw_winners(&snapshot, &seed, number_of_winners);
+        if (draws.len() as u64) < number_of_winners {
+            log::warn!(
+                "Could only draw {} winners (not enough wallets with balance)",
+                draws.len()
+            );
+        }
+
+        let total_balance: u64 = snapshot
+            .0
+            .iter()
+            .map(|entry| entry.token_balance_atomic)
+            .sum();
+        let winner_balances: HashMap<Pubkey, u64> = draws
+            .iter()
+            .map(|draw| {
+                // UNWRAP-SAFETY: wallet_pubkey of a draw comes from a parsed snapshot entry
+                (
+                    Pubkey::from_str(&draw.wallet_pubkey).unwrap(),
+                    draw.token_balance_atomic,
+                )
+            })
+            .collect();
+        let mut wallet_list = WalletList(
+            winner_balances
+                .into_iter()
+                .map(|(wallet_pubkey, token_balance_atomic)| WalletListEntry {
+                    wallet_pubkey,
+                    amount_to_airdrop: amount_per_winner,
+                    provenance: Some(Provenance {
+                        source_balance_atomic: token_balance_atomic,
+                        weight: token_balance_atomic as f64 / total_balance as f64,
+                        multiplier: 1.0,
+                        bound_applied: Default::default(),
+                        rounding_adjustment: 0.0,
+                    }),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        log::info!("Total wallet list count: {}", wallet_list.0.len());
+
+        let proof = RaffleProof {
+            algorithm: RAFFLE_ALGORITHM.to_string(),
+            seed: to_hex(&seed),
+            seed_source,
+            snapshot_hash: hash_snapshot(&snapshot),
+            number_of_winners,
+            amount_per_winner,
+            draws,
+        };
+
+        if !args.dry_run {
+            proof
+                .save_to_path(&proof_path)
+                .unwrap_or_else(|err| log::error!("Failed to save raffle proof: {err:?}"));
+            wallet_list
+                .save_to_path(&wallet_list_path)
+                .unwrap_or_else(|err| log::error!("Failed to save status list: {err:?}"));
+        }
+
+        Ok(())
+    }
+}
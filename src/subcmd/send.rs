@@ -1,19 +1,39 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{collections::HashSet, path::PathBuf, str::FromStr, time::Duration};
 
 use clap::{
     builder::{StringValueParser, TypedValueParser},
     Args,
 };
-use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file, signer::Signer};
+use solana_sdk::{
+    address_lookup_table::{state::LOOKUP_TABLE_MAX_ADDRESSES, AddressLookupTableAccount},
+    pubkey::Pubkey,
+    signature::read_keypair_file,
+    signer::Signer,
+};
 use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{amount_to_ui_amount_string_trimmed, extension::transfer_fee::TransferFee};
 
 use crate::{
-    consts::{DEFAULT_COMPUTE_UNIT_LIMIT, DEFAULT_COMPUTE_UNIT_PRICE},
-    data::{CsvListSerde, WalletList},
+    consts::{
+        DEFAULT_BLOCKHASH_REFRESH_INTERVAL_SECS, DEFAULT_COMPUTE_UNIT_LIMIT,
+        DEFAULT_COMPUTE_UNIT_MARGIN_PERCENT, DEFAULT_COMPUTE_UNIT_PRICE,
+        DEFAULT_MAX_COMPUTE_UNIT_PRICE, DEFAULT_NONCE_POOL_SIZE,
+        DEFAULT_PRIORITY_FEE_ESCALATION_PERCENT, DEFAULT_PRIORITY_FEE_PERCENTILE,
+        DEFAULT_SEND_CONCURRENCY, LAMPORTS_PER_SIGNATURE,
+    },
+    data::{
+        CsvListSerde, LookupTableList, NonceAccountList, SendJournal, SendRecords, SentTxList,
+        Status, WalletList,
+    },
     errors::{Error, Result},
     subcmd::Subcmd,
     utils::{
-        add_to_filename, create_backup_if_file_exists, get_token_mint_info, prompt_confirmation,
+        add_to_filename, count_lookup_table_setup_txs, create_backup_if_file_exists,
+        create_lookup_tables, create_nonce_accounts, get_epoch_transfer_fee,
+        get_lookup_table_addresses, get_lookup_table_rent, get_mint_extensions, get_mint_metadata,
+        get_nonce_account_rent, get_rpc_client, get_token_account_rent, get_token_mint_info,
+        is_interrupted, pack_ixs, prompt_confirmation, set_interrupt_handler, MintMetadata,
+        PriorityFeePolicy, SendCostEstimate, SendOptions, SendPipeline, TransferHookResolver,
     },
 };
 
@@ -60,6 +80,104 @@ pub struct SendArgs {
         help = "After sending transaction, wait for confirmation before proceeding"
     )]
     should_confirm: bool,
+
+    #[arg(
+        long,
+        help = "Maximum total rent (in lamports) to spend on creating missing atas"
+    )]
+    max_ata_rent_lamports: Option<u64>,
+
+    #[arg(
+        long,
+        help = "For mints with a transfer fee, add the fee on top so that wallets receive the allocated amount"
+    )]
+    gross_up_transfer_fee: bool,
+
+    #[arg(long, short, help = "Skip the confirmation prompt (for automation)")]
+    yes: bool,
+
+    #[arg(
+        long,
+        help = "Create address lookup tables for the accounts of the airdrop to fit more transfers per tx (close them with close-lookup-tables afterwards)"
+    )]
+    use_lookup_tables: bool,
+
+    #[arg(
+        long,
+        help = "Simulate each tx and set its compute unit limit to the consumed units plus margin (falls back to --compute-unit-limit if simulation fails)"
+    )]
+    simulate_compute_unit_limit: bool,
+
+    #[arg(
+        long,
+        help = "Margin (in percent of consumed units) added to the simulated compute unit limit",
+        default_value_t = DEFAULT_COMPUTE_UNIT_MARGIN_PERCENT,
+    )]
+    compute_unit_margin_percent: u32,
+
+    #[arg(
+        long,
+        help = "Set the compute unit price of each tx from recent prioritization fees of the accounts it writes (--compute-unit-price becomes the floor)"
+    )]
+    dynamic_compute_unit_price: bool,
+
+    #[arg(
+        long,
+        help = "Percentile of the recent prioritization fees to pay with --dynamic-compute-unit-price",
+        default_value_t = DEFAULT_PRIORITY_FEE_PERCENTILE,
+        value_parser = clap::value_parser!(u8).range(0..=100),
+    )]
+    priority_fee_percentile: u8,
+
+    #[arg(
+        long,
+        help = "Maximum compute unit price in micro lamports with --dynamic-compute-unit-price",
+        default_value_t = DEFAULT_MAX_COMPUTE_UNIT_PRICE,
+    )]
+    max_compute_unit_price: u64,
+
+    #[arg(
+        long,
+        help = "Increase (in percent) over the last compute unit price for txs retried with --dynamic-compute-unit-price",
+        default_value_t = DEFAULT_PRIORITY_FEE_ESCALATION_PERCENT,
+    )]
+    priority_fee_escalation_percent: u32,
+
+    #[arg(
+        long,
+        help = "Build txs on durable nonces from a pool of nonce accounts owned by the payer (created if needed), so that they do not expire"
+    )]
+    use_durable_nonce: bool,
+
+    #[arg(
+        long,
+        help = "Number of nonce accounts in the pool with --use-durable-nonce",
+        default_value_t = DEFAULT_NONCE_POOL_SIZE,
+        value_parser = clap::value_parser!(u64).range(1..=100).map(|n| n as usize),
+    )]
+    nonce_pool_size: usize,
+
+    #[arg(
+        long,
+        help = "Number of txs submitted in parallel",
+        default_value_t = DEFAULT_SEND_CONCURRENCY,
+        value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize),
+    )]
+    concurrency: usize,
+
+    #[arg(
+        long,
+        help = "Maximum number of txs submitted per second",
+        value_parser = clap::value_parser!(u32).range(1..),
+    )]
+    max_tps: Option<u32>,
+
+    #[arg(
+        long,
+        help = "Interval (in seconds) at which the blockhash shared by txs is refreshed",
+        default_value_t = DEFAULT_BLOCKHASH_REFRESH_INTERVAL_SECS,
+    )]
+    blockhash_refresh_interval_secs: u64,
 }
 
 impl SendArgs {
@@ -71,12 +189,27 @@ impl SendArgs {
             compute_unit_limit,
             compute_unit_price,
             should_confirm,
+            max_ata_rent_lamports,
+            gross_up_transfer_fee,
+            yes,
+            use_lookup_tables,
+            simulate_compute_unit_limit,
+            compute_unit_margin_percent,
+            dynamic_compute_unit_price,
+            priority_fee_percentile,
+            max_compute_unit_price,
+            priority_fee_escalation_percent,
+            use_durable_nonce,
+            nonce_pool_size,
+            concurrency,
+            max_tps,
+            blockhash_refresh_interval_secs,
         } = match args.subcmd {
             Subcmd::Send(a) => a,
             _ => unreachable!(),
         };
 
-        let rpc_client = args.config.rpc_client();
+        let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
         let (token_program_id, token_decimals) =
             get_token_mint_info(&rpc_client, &airdrop_token_mint_pubkey)?;
         let payer = read_keypair_file(
@@ -90,13 +223,77 @@ impl SendArgs {
             &airdrop_token_mint_pubkey,
             &token_program_id,
         );
+        let mint_extensions = get_mint_extensions(&rpc_client, &airdrop_token_mint_pubkey)?;
+        let (mut transfer_fee, mut transfer_fee_change_epoch) =
+            match mint_extensions.transfer_fee_config.as_ref() {
+                Some(transfer_fee_config) => {
+                    let (epoch, transfer_fee, transfer_fee_change_epoch) =
+                        get_epoch_transfer_fee(&rpc_client, transfer_fee_config)?;
+                    log_transfer_fee(epoch, &transfer_fee, transfer_fee_change_epoch);
+                    (Some(transfer_fee), transfer_fee_change_epoch)
+                }
+                None => {
+                    if gross_up_transfer_fee {
+                        log::warn!("Mint has no transfer fee, ignoring --gross-up-transfer-fee");
+                    }
+                    (None, None)
+                }
+            };
+        let priority_fee_policy = match dynamic_compute_unit_price {
+            true => {
+                if max_compute_unit_price < compute_unit_price {
+                    log::warn!("--max-compute-unit-price is below --compute-unit-price, capping at {max_compute_unit_price}");
+                }
+                Some(PriorityFeePolicy {
+                    percentile: priority_fee_percentile,
+                    min_compute_unit_price: compute_unit_price,
+                    max_compute_unit_price,
+                    escalation_percent: priority_fee_escalation_percent,
+                })
+            }
+            false => None,
+        };
+        let transfer_hook_resolver = mint_extensions.transfer_hook_program_id.map(|program_id| {
+            log::info!("Mint has transfer hook program {program_id}, resolving extra accounts");
+            TransferHookResolver::new(&rpc_client)
+        });
 
         // Note: assume that the either check stage or confirmed stage ran beforehand
         let check_stage_save_path = add_to_filename(&wallet_list_path, "checked");
         let confirm_stage_save_path = add_to_filename(&wallet_list_path, "confirmed");
         let current_stage_save_path = add_to_filename(&wallet_list_path, "sent");
+        let lookup_table_list_path = LookupTableList::path_from_wallet_list_path(&wallet_list_path);
+        let sent_tx_list_path = SentTxList::path_from_wallet_list_path(&wallet_list_path);
+        let send_journal_path = SendJournal::path_from_wallet_list_path(&wallet_list_path);
+        let nonce_account_list_path =
+            NonceAccountList::path_from_wallet_list_path(&wallet_list_path);
+        let mut sent_tx_list = if sent_tx_list_path.try_exists()? {
+            SentTxList::parse_from_path(&sent_tx_list_path)?
+        } else {
+            SentTxList::default()
+        };
 
-        let mut wallet_list = if confirm_stage_save_path.try_exists()? {
+        let mut resumed_sigs = Vec::new();
+        let mut wallet_list = if send_journal_path.try_exists()? {
+            log::info!("Detected send journal of an interrupted send, resuming ...");
+            if !current_stage_save_path.try_exists()? {
+                return Err(Error::SendJournalError(
+                    "Could not find the sent stage checkpoint".to_string(),
+                ));
+            }
+            let mut wallet_list = WalletList::parse_list_from_path(&current_stage_save_path)?;
+            for record in SendJournal::parse_from_path(&send_journal_path)? {
+                let sig = wallet_list.apply_send_journal_record(&record)?;
+                sent_tx_list.insert(&sig, record.sent_tx);
+                resumed_sigs.push(sig);
+            }
+            log::info!(
+                "Recovered {} txs submitted by the interrupted send, rebroadcasting ...",
+                resumed_sigs.len()
+            );
+            wallet_list.rebroadcast_unconfirmed(&rpc_client, &sent_tx_list, args.dry_run)?;
+            wallet_list
+        } else if confirm_stage_save_path.try_exists()? {
             log::info!("Detected saved confirm stage, retrying confirmation ...");
             let mut wallet_list = WalletList::parse_list_from_path(&confirm_stage_save_path)?;
             // NOTE: make sure the confirm stage file from last send attempt is cleared (saved as backup) for the next confirm stage
@@ -105,12 +302,18 @@ impl SendArgs {
             }
             if wallet_list.count_unconfirmed() != 0 {
                 log::info!("Attempting to confirm unconfirmed trnasactions ...");
-                let n_total_unconfirmed = wallet_list.confirm(&rpc_client);
-                log::info!("Resetting {n_total_unconfirmed} to failed");
-                wallet_list.set_unconfirmed_to_failed();
+                let n_pending = wallet_list.confirm(&rpc_client, &sent_tx_list);
+                // NOTE: pending txs might still land, so they are rebroadcast as is instead of being rebuilt
+                let n_rebroadcast = wallet_list.rebroadcast_unconfirmed(
+                    &rpc_client,
+                    &sent_tx_list,
+                    args.dry_run,
+                )?;
+                log::info!("Rebroadcast {n_rebroadcast} of {n_pending} pending txs");
             }
-            log::info!("Resetting failed to qualified");
+            log::info!("Resetting failed and expired to qualified");
             wallet_list.set_failed_to_qualified();
+            wallet_list.set_expired_to_qualified();
             wallet_list
         } else if check_stage_save_path.try_exists()? {
             if current_stage_save_path.try_exists()? {
@@ -121,30 +324,386 @@ impl SendArgs {
             return Err(Error::StageNotReady);
         };
 
-        if !args.dry_run && !prompt_confirmation("About to send txs. Should we proceed?") {
-            log::info!("Terminating");
-            return Ok(());
-        }
-        log::info!("Transferring the airdrop ...",);
-        wallet_list.transfer_airdrop(
-            &rpc_client,
+        let entries_ixs_with_idx = wallet_list.build_transfer_ixs(
             &airdrop_token_mint_pubkey,
             &token_program_id,
             token_decimals,
             &source_ata,
             &payer,
+            transfer_fee.as_ref(),
+            gross_up_transfer_fee,
+            transfer_hook_resolver.as_ref(),
+        );
+        let use_lookup_tables = if use_lookup_tables && args.dry_run {
+            log::warn!(
+                "Lookup tables are not created in dry run, ignoring --use-lookup-tables \
+                 (simulated txs are packed without them and differ from the ones that would be sent)"
+            );
+            false
+        } else {
+            use_lookup_tables
+        };
+        let lookup_table_addresses = match use_lookup_tables {
+            true => get_lookup_table_addresses(
+                entries_ixs_with_idx
+                    .iter()
+                    .flat_map(|(_idx, ixs)| ixs.iter()),
+            ),
+            false => Vec::new(),
+        };
+        // NOTE: placeholder keys are only used for packing, the actual tables are created right before sending
+        let mut lookup_table_accounts: Vec<AddressLookupTableAccount> = lookup_table_addresses
+            .chunks(LOOKUP_TABLE_MAX_ADDRESSES)
+            .map(|addresses| AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: addresses.to_vec(),
+            })
+            .collect();
+        let use_durable_nonce = if use_durable_nonce && args.dry_run {
+            log::warn!(
+                "Nonce accounts are not used in dry run, ignoring --use-durable-nonce \
+                 (simulated txs use a recent blockhash and lack the advance nonce ix)"
+            );
+            false
+        } else {
+            use_durable_nonce
+        };
+        let mut nonce_account_list = match use_durable_nonce {
+            true if nonce_account_list_path.try_exists()? => {
+                Some(NonceAccountList::parse_from_path(&nonce_account_list_path)?)
+            }
+            true => Some(NonceAccountList::default()),
+            false => None,
+        };
+        if let Some(nonce_account_list) = nonce_account_list.as_mut() {
+            // NOTE: nonce accounts used by the interrupted send stay in use until their txs land
+            for sig in resumed_sigs.iter() {
+                if let Some((nonce_pubkey, nonce)) = sent_tx_list.durable_nonce(sig)? {
+                    nonce_account_list.set_last_used_nonce(&nonce_pubkey, &nonce);
+                }
+            }
+        }
+        let nonce_accounts_to_create_count = nonce_account_list
+            .as_ref()
+            .map(|nonce_account_list| {
+                nonce_pool_size.saturating_sub(nonce_account_list.nonce_accounts.len())
+            })
+            .unwrap_or_default();
+        let batches = pack_ixs(
+            &payer.pubkey(),
+            entries_ixs_with_idx,
+            &lookup_table_accounts,
+            compute_unit_limit,
+            use_durable_nonce,
+        );
+        let mut total_lookup_table_lamports = 0;
+        for lookup_table_account in lookup_table_accounts.iter() {
+            let n_addresses = lookup_table_account.addresses.len();
+            total_lookup_table_lamports += get_lookup_table_rent(&rpc_client, n_addresses)?
+                + count_lookup_table_setup_txs(n_addresses) as u64 * LAMPORTS_PER_SIGNATURE;
+        }
+        let total_nonce_account_lamports = match nonce_accounts_to_create_count {
+            0 => 0,
+            // NOTE: creation txs are signed by both the payer and the new nonce account
+            _ => {
+                (get_nonce_account_rent(&rpc_client)? + 2 * LAMPORTS_PER_SIGNATURE)
+                    * nonce_accounts_to_create_count as u64
+            }
+        };
+
+        let atas_to_create_count = wallet_list.count_atas_to_create(&payer.pubkey());
+        let total_ata_rent = match atas_to_create_count {
+            0 => 0,
+            _ => {
+                get_token_account_rent(&rpc_client, &airdrop_token_mint_pubkey, &token_program_id)?
+                    * atas_to_create_count as u64
+            }
+        };
+        if max_ata_rent_lamports.is_some_and(|max| total_ata_rent > max) {
+            log::error!("Total ata rent exceeds --max-ata-rent-lamports");
+            return Err(Error::AtaRentBudgetExceeded(total_ata_rent));
+        }
+
+        // preflight: make sure the payer can afford the whole airdrop before sending anything
+        let cost_estimate = SendCostEstimate::new(
+            batches.len(),
+            wallet_list.sum_transfer_amount_and_fee(
+                &payer.pubkey(),
+                transfer_fee.as_ref(),
+                gross_up_transfer_fee,
+                |_idx, status| matches!(status, Status::Qualified),
+            ),
+            compute_unit_limit,
+            // NOTE: dynamic prices are capped, so the cap is an upper bound
+            priority_fee_policy
+                .map(|priority_fee_policy| priority_fee_policy.max_compute_unit_price)
+                .unwrap_or(compute_unit_price),
+            total_ata_rent,
+            total_lookup_table_lamports,
+            total_nonce_account_lamports,
+        );
+        // NOTE: pending txs (rebroadcast above) may still land and debit the source account too
+        let (pending_amount_atomic, _) = wallet_list.sum_transfer_amount_and_fee(
+            &payer.pubkey(),
+            transfer_fee.as_ref(),
+            gross_up_transfer_fee,
+            |_idx, status| matches!(status, Status::Unconfirmed(_)),
+        );
+        // NOTE: a missing source account holds nothing
+        let source_balance_atomic = rpc_client
+            .get_token_account_balance(&source_ata)
+            .map_or(0, |balance| {
+                balance.amount.parse::<u64>().unwrap_or_default()
+            });
+        let payer_balance_lamports = rpc_client.get_balance(&payer.pubkey())?;
+        let total_ui_amount =
+            amount_to_ui_amount_string_trimmed(cost_estimate.total_amount_atomic, token_decimals);
+        let mint_metadata = get_mint_metadata(&rpc_client, &airdrop_token_mint_pubkey);
+        log::info!(
+            "Airdrop summary:\n\
+             cluster: {}\n\
+             rpc: {}\n\
+             mint: {airdrop_token_mint_pubkey} ({}{token_decimals} decimals, program {token_program_id})\n\
+             payer: {}\n\
+             source account: {source_ata} (balance {})\n\
+             recipients: {} in {} txs\n\
+             total amount: {total_ui_amount} (incl. {} transfer fee)\n\
+             pending amount: {} (unconfirmed txs that may still land)\n\
+             signature fees: {} lamports\n\
+             priority fees: {} lamports\n\
+             ata rent: {} lamports ({atas_to_create_count} atas)\n\
+             lookup tables: {} lamports ({} tables)\n\
+             nonce accounts: {} lamports ({nonce_accounts_to_create_count} to create)\n\
+             total: {} lamports (payer balance {payer_balance_lamports} lamports)",
+            args.detected_cluster
+                .map_or("not detected".to_string(), |cluster| cluster.to_string()),
+            rpc_client.url(),
+            mint_metadata
+                .as_ref()
+                .map(|MintMetadata { name, symbol }| format!("{name} / {symbol}, "))
+                .unwrap_or_default(),
+            payer.pubkey(),
+            amount_to_ui_amount_string_trimmed(source_balance_atomic, token_decimals),
+            batches.iter().map(|batch| batch.len()).sum::<usize>(),
+            cost_estimate.n_txs,
+            amount_to_ui_amount_string_trimmed(
+                cost_estimate.total_transfer_fee_atomic,
+                token_decimals
+            ),
+            amount_to_ui_amount_string_trimmed(pending_amount_atomic, token_decimals),
+            cost_estimate.signature_fees_lamports,
+            cost_estimate.priority_fees_lamports,
+            cost_estimate.ata_rent_lamports,
+            cost_estimate.lookup_table_lamports,
+            lookup_table_accounts.len(),
+            cost_estimate.nonce_account_lamports,
+            cost_estimate.total_lamports(),
+        );
+        let required_amount_atomic = cost_estimate.total_amount_atomic + pending_amount_atomic;
+        if required_amount_atomic > source_balance_atomic {
+            let shortfall = required_amount_atomic - source_balance_atomic;
+            log::error!(
+                "Source account is short of {} tokens",
+                amount_to_ui_amount_string_trimmed(shortfall, token_decimals)
+            );
+            return Err(Error::InsufficientTokenBalance(shortfall));
+        }
+        if cost_estimate.total_lamports() > payer_balance_lamports {
+            let shortfall = cost_estimate.total_lamports() - payer_balance_lamports;
+            log::error!("Payer is short of {shortfall} lamports");
+            return Err(Error::InsufficientSolBalance(shortfall));
+        }
+
+        let mut expected_confirmations = vec![total_ui_amount.as_str()];
+        if let Some(MintMetadata { symbol, .. }) = mint_metadata.as_ref() {
+            // NOTE: an empty symbol would let an empty input through
+            if !symbol.is_empty() {
+                expected_confirmations.push(symbol);
+            }
+        }
+        if !args.dry_run
+            && !yes
+            && !prompt_confirmation(
+                "About to send txs. Should we proceed?",
+                &expected_confirmations,
+            )
+        {
+            log::info!("Terminating");
+            return Ok(());
+        }
+        // NOTE: installed after the prompt, so that Ctrl-C at the prompt still aborts right away
+        set_interrupt_handler()?;
+        if !lookup_table_accounts.is_empty() {
+            lookup_table_accounts =
+                create_lookup_tables(&rpc_client, &payer, &lookup_table_addresses)?;
+            // NOTE: kept so that close-lookup-tables can reclaim the rent later
+            let mut lookup_table_list = if lookup_table_list_path.try_exists()? {
+                LookupTableList::parse_from_path(&lookup_table_list_path)?
+            } else {
+                LookupTableList::default()
+            };
+            lookup_table_list.lookup_tables.extend(
+                lookup_table_accounts
+                    .iter()
+                    .map(|lookup_table_account| lookup_table_account.key.to_string()),
+            );
+            lookup_table_list.save_to_path(&lookup_table_list_path)?;
+        }
+        if let Some(nonce_account_list) = nonce_account_list.as_mut() {
+            if nonce_accounts_to_create_count != 0 {
+                let nonce_pubkeys =
+                    create_nonce_accounts(&rpc_client, &payer, nonce_accounts_to_create_count)?;
+                nonce_account_list.add(&nonce_pubkeys);
+                nonce_account_list.save_to_path(&nonce_account_list_path)?;
+            }
+        }
+        if is_interrupted() {
+            log::info!("Terminating before sending any tx");
+            return Err(Error::Interrupted);
+        }
+        // checkpoint: when interrupted, the journal of this send is replayed on top of the sent stage
+        // NOTE: stage files are saved sorted, so saving does not move the entries the batch idxs point at
+        let mut send_journal = match args.dry_run {
+            true => None,
+            false => {
+                wallet_list.save_to_path(&current_stage_save_path)?;
+                sent_tx_list.save_to_path(&sent_tx_list_path)?;
+                Some(SendJournal::create(&send_journal_path)?)
+            }
+        };
+        log::info!("Transferring the airdrop ...",);
+        let send_options = SendOptions {
+            address_lookup_table_accounts: &lookup_table_accounts,
             compute_unit_limit,
             compute_unit_price,
-            args.dry_run,
+            priority_fee_policy,
+            compute_unit_margin_percent: simulate_compute_unit_limit
+                .then_some(compute_unit_margin_percent),
+            pipeline: SendPipeline {
+                concurrency,
+                max_tps,
+                blockhash_refresh_interval: Duration::from_secs(blockhash_refresh_interval_secs),
+            },
+            dry_run: args.dry_run,
             should_confirm,
+            transfer_fee_change_epoch,
+            source_ata,
+        };
+        // NOTE: only the entries queued by this run are summarized, not the ones carried over
+        //  (rebroadcast or resumed) from an earlier send
+        let (mut recipients_sent_count, mut total_amount_sent, mut total_fee_withheld) = (0, 0, 0);
+        let mut batches = batches;
+        loop {
+            let queued_idxs: HashSet<usize> = batches
+                .iter()
+                .flatten()
+                .map(|((idx, _wallet_pubkey), _ixs)| *idx)
+                .collect();
+            let transfer_fee_changed = wallet_list.transfer_airdrop(
+                &rpc_client,
+                &payer,
+                SendRecords {
+                    sent_tx_list: &mut sent_tx_list,
+                    nonce_account_list: nonce_account_list.as_mut(),
+                    send_journal: send_journal.as_mut(),
+                },
+                batches,
+                &SendOptions {
+                    transfer_fee_change_epoch,
+                    ..send_options
+                },
+            );
+            let is_sent = |idx: usize, status: &Status| {
+                queued_idxs.contains(&idx) && matches!(status, Status::Unconfirmed(_))
+            };
+            recipients_sent_count += wallet_list
+                .0
+                .iter()
+                .enumerate()
+                .filter(|(idx, entry)| is_sent(*idx, &entry.status))
+                .count();
+            let (amount_sent, fee_withheld) = wallet_list.sum_transfer_amount_and_fee(
+                &payer.pubkey(),
+                transfer_fee.as_ref(),
+                gross_up_transfer_fee,
+                is_sent,
+            );
+            total_amount_sent += amount_sent;
+            total_fee_withheld += fee_withheld;
+            if !transfer_fee_changed || is_interrupted() {
+                break;
+            }
+
+            // the transfer ixs carry the fee of the epoch they were built in, so the remaining
+            // entries are rebuilt with the fee of the new epoch
+            // UNWRAP-SAFETY: the transfer fee can only change for mints with a transfer fee config
+            let (epoch, new_transfer_fee, new_transfer_fee_change_epoch) = get_epoch_transfer_fee(
+                &rpc_client,
+                mint_extensions.transfer_fee_config.as_ref().unwrap(),
+            )?;
+            log_transfer_fee(epoch, &new_transfer_fee, new_transfer_fee_change_epoch);
+            transfer_fee = Some(new_transfer_fee);
+            transfer_fee_change_epoch = new_transfer_fee_change_epoch;
+            let entries_ixs_with_idx = wallet_list.build_transfer_ixs(
+                &airdrop_token_mint_pubkey,
+                &token_program_id,
+                token_decimals,
+                &source_ata,
+                &payer,
+                transfer_fee.as_ref(),
+                gross_up_transfer_fee,
+                transfer_hook_resolver.as_ref(),
+            );
+            batches = pack_ixs(
+                &payer.pubkey(),
+                entries_ixs_with_idx,
+                &lookup_table_accounts,
+                compute_unit_limit,
+                use_durable_nonce,
+            );
+        }
+        // NOTE: submitted txs may still fail or expire, run confirm to see what landed
+        log::info!(
+            "Campaign summary: submitted transfers to {recipients_sent_count} recipients in this run, \
+             sending {} in total, {} withheld as transfer fee once they land (run confirm to see what landed)",
+            amount_to_ui_amount_string_trimmed(total_amount_sent, token_decimals),
+            amount_to_ui_amount_string_trimmed(total_fee_withheld, token_decimals),
         );
 
         if !args.dry_run {
-            wallet_list
-                .save_to_path(&current_stage_save_path)
-                .unwrap_or_else(|err| log::error!("Failed to save status list: {err:?}"));
+            if let Some(nonce_account_list) = nonce_account_list.as_ref() {
+                nonce_account_list
+                    .save_to_path(&nonce_account_list_path)
+                    .unwrap_or_else(|err| {
+                        log::error!("Failed to save nonce account list: {err:?}")
+                    });
+            }
+            // NOTE: the journal is only removed once everything in it is saved
+            sent_tx_list.save_to_path(&sent_tx_list_path)?;
+            wallet_list.save_to_path(&current_stage_save_path)?;
+            drop(send_journal);
+            std::fs::remove_file(&send_journal_path)?;
+        }
+        if is_interrupted() {
+            log::warn!("Send was interrupted, unsent entries stay qualified. Run confirm, then send again to resume");
+            return Err(Error::Interrupted);
         }
 
         Ok(())
     }
 }
+
+fn log_transfer_fee(
+    epoch: u64,
+    transfer_fee: &TransferFee,
+    transfer_fee_change_epoch: Option<u64>,
+) {
+    log::info!(
+        "Mint charges a transfer fee of {} bps (max {}) in epoch {epoch}",
+        u16::from(transfer_fee.transfer_fee_basis_points),
+        u64::from(transfer_fee.maximum_fee),
+    );
+    if let Some(transfer_fee_change_epoch) = transfer_fee_change_epoch {
+        log::info!("Transfer fee changes in epoch {transfer_fee_change_epoch}, the remaining transfers are rebuilt then");
+    }
+}
@@ -17,15 +17,13 @@ use solana_sdk::{
 };
 
 use crate::{
-    consts::DEFAULT_SNAPSHOT_MINIMUM_BALANCE_ATOMIC,
+    consts::{DEFAULT_SNAPSHOT_MINIMUM_BALANCE_ATOMIC, MINT_OFFSET, OWNER_OFFSET},
     data::{CsvListSerde, Snapshot, SnapshotEntry},
     errors::{Error, Result},
     subcmd::Subcmd,
-    utils::get_token_mint_info,
+    utils::{get_rpc_client, get_token_mint_info},
 };
 
-const MINT_OFFSET: usize = 0;
-const OWNER_OFFSET: usize = 32;
 const OWNER_LENGTH: usize = 32;
 const AMOUNT_LENGTH: usize = 8;
 
@@ -97,7 +95,7 @@ impl SnapshotArgs {
             "Taking token snapshot for {:?}...",
             snapshot_token_mint_pubkey
         );
-        let rpc_client = args.config.rpc_client();
+        let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
 
         let mut snapshot = take_snapshot(
             &rpc_client,
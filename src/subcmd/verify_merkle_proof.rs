@@ -0,0 +1,111 @@
+use std::{path::PathBuf, str::FromStr};
+
+use clap::{
+    builder::{StringValueParser, TypedValueParser},
+    Args,
+};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+
+use crate::{
+    data::{hash_leaf, verify_proof, MerkleDistributorTree},
+    errors::{Error, Result},
+    subcmd::Subcmd,
+};
+
+#[derive(Args, Debug)]
+#[command(long_about = "Verify a merkle proof of a claimant against a merkle root (offline)")]
+pub struct VerifyMerkleProofArgs {
+    #[arg(
+        long,
+        short,
+        help = "Wallet pubkey of the claimant",
+        value_parser = StringValueParser::new().try_map(|s| Pubkey::from_str(&s)),
+    )]
+    claimant: Pubkey,
+
+    #[arg(
+        long,
+        short,
+        help = "Path to merkle tree json file to read root, amounts and proof of the claimant from",
+        conflicts_with_all = ["merkle_root", "amount_unlocked", "amount_locked", "proof"],
+        required_unless_present = "merkle_root"
+    )]
+    merkle_tree_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        short = 'r',
+        help = "Merkle root",
+        value_parser = StringValueParser::new().try_map(|s| Hash::from_str(&s)),
+        requires = "amount_unlocked"
+    )]
+    merkle_root: Option<Hash>,
+
+    #[arg(
+        long,
+        short = 'u',
+        help = "Unlocked amount (in token atomic) of the claim"
+    )]
+    amount_unlocked: Option<u64>,
+
+    #[arg(
+        long,
+        short = 'l',
+        help = "Locked amount (in token atomic) of the claim",
+        default_value_t = 0
+    )]
+    amount_locked: u64,
+
+    #[arg(long, short, help = "Proof hashes, from leaf level to root level")]
+    proof: Vec<String>,
+}
+
+impl VerifyMerkleProofArgs {
+    pub fn run(args: crate::Args) -> Result<()> {
+        let Self {
+            claimant,
+            merkle_tree_path,
+            merkle_root,
+            amount_unlocked,
+            amount_locked,
+            proof,
+        } = match args.subcmd {
+            Subcmd::VerifyMerkleProof(a) => a,
+            _ => unreachable!(),
+        };
+
+        let (merkle_root, amount_unlocked, amount_locked, proof) = match merkle_tree_path {
+            Some(path) => {
+                let merkle_tree = MerkleDistributorTree::parse_from_path(&path)?;
+                let node = merkle_tree
+                    .find_node(&claimant)
+                    .ok_or_else(|| Error::ClaimantNotFound(claimant.to_string()))?
+                    .clone();
+                (
+                    Hash::from_str(&merkle_tree.merkle_root)?,
+                    node.amount_unlocked,
+                    node.amount_locked,
+                    node.proof,
+                )
+            }
+            // UNWRAP-SAFETY: clap guarantees merkle_root and amount_unlocked when merkle_tree_path is absent
+            None => (
+                merkle_root.unwrap(),
+                amount_unlocked.unwrap(),
+                amount_locked,
+                proof,
+            ),
+        };
+
+        let leaf = hash_leaf(&claimant, amount_unlocked, amount_locked);
+        if verify_proof(&merkle_root, leaf, &proof)? {
+            log::info!(
+                "Valid proof: {claimant} can claim {amount_unlocked} unlocked and {amount_locked} locked"
+            );
+            Ok(())
+        } else {
+            log::error!("Invalid proof for {claimant} against root {merkle_root}");
+            Err(Error::InvalidMerkleProof)
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::{
+    data::{
+        draw_winners, hash_snapshot, CsvListSerde, RaffleProof, RaffleSeedSource, Snapshot,
+        RAFFLE_ALGORITHM,
+    },
+    errors::{Error, Result},
+    subcmd::Subcmd,
+    utils::{from_hex, get_blockhash_at_or_after_slot, get_rpc_client},
+};
+
+#[derive(Args, Debug)]
+#[command(long_about = "Given a token snapshot and a raffle proof, reproduce and verify the draw")]
+pub struct VerifyRaffleArgs {
+    #[arg(long, short, help = "Path to token snapshot csv file")]
+    snapshot_path: PathBuf,
+
+    #[arg(long, short = 'o', help = "Path to raffle proof json file")]
+    proof_path: PathBuf,
+}
+
+impl VerifyRaffleArgs {
+    pub fn run(args: crate::Args) -> Result<()> {
+        let Self {
+            snapshot_path,
+            proof_path,
+        } = match args.subcmd {
+            Subcmd::VerifyRaffle(a) => a,
+            _ => unreachable!(),
+        };
+
+        let snapshot = Snapshot::parse_list_from_path(&snapshot_path)?;
+        let proof = RaffleProof::parse_from_path(&proof_path)?;
+
+        if proof.algorithm != RAFFLE_ALGORITHM {
+            return Err(Error::RaffleVerificationFailed(format!(
+                "Unsupported algorithm: {}",
+                proof.algorithm
+            )));
+        }
+        if proof.snapshot_hash != hash_snapshot(&snapshot) {
+            return Err(Error::RaffleVerificationFailed(
+                "Snapshot does not match the one used for the raffle".to_string(),
+            ));
+        }
+
+        let seed = from_hex(&proof.seed)?;
+        // NOTE: draw_start_slot is written by whoever ran the raffle and is not anchored on chain,
+        //  so it can't prove the seed slot was still in the future and is not checked here
+        if let RaffleSeedSource::Blockhash {
+            requested_slot,
+            slot,
+            blockhash,
+            ..
+        } = &proof.seed_source
+        {
+            log::info!("Verifying seed against blockhash of slot {slot} ...");
+            let rpc_client = get_rpc_client(&args.config, args.cluster_profile.as_ref());
+            let (actual_slot, actual_blockhash) =
+                get_blockhash_at_or_after_slot(&rpc_client, *requested_slot)?;
+            if actual_slot != *slot
+                || actual_blockhash.to_string() != *blockhash
+                || actual_blockhash.to_bytes().as_slice() != seed.as_slice()
+            {
+                return Err(Error::RaffleVerificationFailed(format!(
+                    "Seed does not match blockhash {actual_blockhash} of slot {actual_slot}"
+                )));
+            }
+        }
+
+        log::info!("Reproducing {} draws ...", proof.number_of_winners);
+        let draws = draw_winners(&snapshot, &seed, proof.number_of_winners);
+        if let Some((expected, actual)) = proof
+            .draws
+            .iter()
+            .zip(draws.iter())
+            .find(|(expected, actual)| expected != actual)
+        {
+            return Err(Error::RaffleVerificationFailed(format!(
+                "Draw #{} mismatch: expected {expected:?}, got {actual:?}",
+                expected.index
+            )));
+        }
+        if proof.draws.len() != draws.len() {
+            return Err(Error::RaffleVerificationFailed(format!(
+                "Expected {} draws, got {}",
+                proof.draws.len(),
+                draws.len()
+            )));
+        }
+
+        log::info!("Raffle proof verified: {} winners", draws.len());
+
+        Ok(())
+    }
+}
@@ -1,10 +1,15 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use clap::Args;
+use solana_sdk::pubkey::Pubkey;
 
 use crate::{
-    data::{CsvListSerde, Snapshot, SnapshotEntry, WalletList, WalletListEntry},
-    errors::Result,
+    consts::MULTIPLIER_PRECISION,
+    data::{
+        BoundApplied, CsvListSerde, MultiplierList, Provenance, Snapshot, SnapshotEntry,
+        WalletList, WalletListEntry,
+    },
+    errors::{Error, Result},
     subcmd::Subcmd,
 };
 
@@ -19,6 +24,25 @@ pub struct WalletListArgs {
 
     #[arg(long, short, help = "Path to token snapshot csv file")]
     snapshot_path: PathBuf,
+
+    #[arg(
+        long,
+        short = 'm',
+        help = "Path to multiplier list csv file in the format of \"wallet_pubkey,multiplier\" (non-negative, wallets not in the list get 1)"
+    )]
+    multiplier_list_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "The maximum amount (in token atomic) a single wallet can receive"
+    )]
+    cap: Option<u64>,
+
+    #[arg(
+        long,
+        help = "The minimum amount (in token atomic) a single wallet with a non-zero weight receives, fails if the total then exceeds the amount to airdrop"
+    )]
+    floor: Option<u64>,
 }
 
 impl WalletListArgs {
@@ -27,60 +51,31 @@ impl WalletListArgs {
             wallet_list_path,
             amount_to_airdrop,
             snapshot_path,
+            multiplier_list_path,
+            cap,
+            floor,
         } = match args.subcmd {
             Subcmd::WalletList(a) => a,
             _ => unreachable!(),
         };
 
         let snapshot = Snapshot::parse_list_from_path(&snapshot_path)?;
+        let multipliers = match multiplier_list_path {
+            Some(path) => MultiplierList::parse_list_from_path(&path)?,
+            None => MultiplierList::default(),
+        }
+        .to_map();
 
-        let total_amount: u64 = snapshot
-            .0
-            .iter()
-            .map(
-                |SnapshotEntry {
-                     token_balance_atomic,
-                     ..
-                 }| token_balance_atomic,
-            )
-            .sum();
-        let mut wallet_list = WalletList(
-            snapshot
-                .0
-                .into_iter()
-                .filter_map(
-                    |SnapshotEntry {
-                         wallet_pubkey,
-                         token_balance_atomic,
-                     }| {
-                        let amount_to_airdrop =
-                            (token_balance_atomic as u128 * amount_to_airdrop as u128
-                                / total_amount as u128) as u64;
-                        if amount_to_airdrop != 0 {
-                            Some(WalletListEntry {
-                                wallet_pubkey,
-                                amount_to_airdrop,
-                                ..Default::default()
-                            })
-                        } else {
-                            None
-                        }
-                    },
-                )
-                .collect::<Vec<_>>(),
-        );
+        let mut wallet_list = allocate(snapshot, &multipliers, amount_to_airdrop, cap, floor)?;
         log::info!("Total wallet list count: {}", wallet_list.0.len());
-
-        let total_amount_from_wallet_list = wallet_list
-            .0
-            .iter()
-            .map(|entry| entry.amount_to_airdrop)
-            .sum::<u64>();
         log::info!(
             "Total amount in wallet list: {}",
-            total_amount_from_wallet_list
+            wallet_list
+                .0
+                .iter()
+                .map(|entry| entry.amount_to_airdrop)
+                .sum::<u64>()
         );
-        assert!(total_amount_from_wallet_list <= total_amount);
 
         if !args.dry_run {
             wallet_list
@@ -91,3 +86,181 @@ impl WalletListArgs {
         Ok(())
     }
 }
+
+/// Splits amount_to_airdrop pro rata to the multiplied snapshot balances, then applies cap and floor.
+/// Wallets that end up with nothing are left out of the list
+fn allocate(
+    snapshot: Snapshot,
+    multipliers: &HashMap<Pubkey, f64>,
+    amount_to_airdrop: u64,
+    cap: Option<u64>,
+    floor: Option<u64>,
+) -> Result<WalletList> {
+    if let (Some(cap), Some(floor)) = (cap, floor) {
+        if floor > cap {
+            return Err(Error::AllocationError(format!(
+                "Floor {floor} is greater than cap {cap}"
+            )));
+        }
+    }
+
+    // (snapshot entry, multiplier, multiplied balance scaled by MULTIPLIER_PRECISION)
+    let weighted_entries: Vec<(SnapshotEntry, f64, u128)> = snapshot
+        .0
+        .into_iter()
+        .map(|entry| {
+            let multiplier = multipliers
+                .get(&entry.wallet_pubkey)
+                .copied()
+                .unwrap_or(1.0);
+            let weighted_balance = entry.token_balance_atomic as u128
+                * (multiplier * MULTIPLIER_PRECISION as f64).round() as u128;
+            (entry, multiplier, weighted_balance)
+        })
+        .collect();
+    let total_weighted_balance: u128 = weighted_entries.iter().map(|(_, _, w)| w).sum();
+    if total_weighted_balance == 0 {
+        return Err(Error::AllocationError(
+            "Total weighted balance is 0 (empty snapshot or all multipliers 0)".to_string(),
+        ));
+    }
+
+    let wallet_list = WalletList(
+        weighted_entries
+            .into_iter()
+            .filter_map(
+                |(
+                    SnapshotEntry {
+                        wallet_pubkey,
+                        token_balance_atomic,
+                    },
+                    multiplier,
+                    weighted_balance,
+                )| {
+                    let numerator = weighted_balance * amount_to_airdrop as u128;
+                    let pro_rata_amount = (numerator / total_weighted_balance) as u64;
+                    let rounding_adjustment = -((numerator % total_weighted_balance) as f64
+                        / total_weighted_balance as f64);
+                    // NOTE: the floor only lifts wallets with some weight, wallets without any
+                    //  (0 balance or multiplier 0) stay out of the list
+                    let (amount_to_airdrop, bound_applied) = match (cap, floor) {
+                        (Some(cap), _) if pro_rata_amount > cap => (cap, BoundApplied::Cap),
+                        (_, Some(floor)) if weighted_balance != 0 && pro_rata_amount < floor => {
+                            (floor, BoundApplied::Floor)
+                        }
+                        _ => (pro_rata_amount, BoundApplied::None),
+                    };
+                    if amount_to_airdrop != 0 {
+                        Some(WalletListEntry {
+                            wallet_pubkey,
+                            amount_to_airdrop,
+                            provenance: Some(Provenance {
+                                source_balance_atomic: token_balance_atomic,
+                                weight: weighted_balance as f64 / total_weighted_balance as f64,
+                                multiplier,
+                                bound_applied,
+                                rounding_adjustment,
+                            }),
+                            ..Default::default()
+                        })
+                    } else {
+                        None
+                    }
+                },
+            )
+            .collect::<Vec<_>>(),
+    );
+
+    // NOTE: pro rata amounts are rounded down and capped, so only the floor can overshoot
+    let total_amount_from_wallet_list = wallet_list
+        .0
+        .iter()
+        .map(|entry| entry.amount_to_airdrop)
+        .sum::<u64>();
+    if total_amount_from_wallet_list > amount_to_airdrop {
+        return Err(Error::AllocationError(format!(
+            "Total amount in wallet list {total_amount_from_wallet_list} exceeds amount to airdrop {amount_to_airdrop}"
+        )));
+    }
+    Ok(wallet_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(balances: &[u64]) -> Snapshot {
+        Snapshot(
+            balances
+                .iter()
+                .enumerate()
+                .map(|(i, token_balance_atomic)| SnapshotEntry {
+                    wallet_pubkey: Pubkey::new_from_array([i as u8 + 1; 32]),
+                    token_balance_atomic: *token_balance_atomic,
+                })
+                .collect(),
+        )
+    }
+
+    fn amounts(wallet_list: &WalletList) -> Vec<u64> {
+        wallet_list.0.iter().map(|e| e.amount_to_airdrop).collect()
+    }
+
+    fn provenance(wallet_list: &WalletList, i: usize) -> &Provenance {
+        wallet_list.0[i].provenance.as_ref().unwrap()
+    }
+
+    #[test]
+    fn splits_by_multiplied_balance() {
+        let multipliers = HashMap::from([(Pubkey::new_from_array([2; 32]), 3.0)]);
+        let wallet_list = allocate(snapshot(&[100, 100]), &multipliers, 1000, None, None).unwrap();
+        assert_eq!(amounts(&wallet_list), vec![250, 750]);
+        assert_eq!(provenance(&wallet_list, 0).multiplier, 1.0);
+        assert_eq!(provenance(&wallet_list, 0).weight, 0.25);
+        assert_eq!(provenance(&wallet_list, 1).multiplier, 3.0);
+        assert_eq!(provenance(&wallet_list, 1).weight, 0.75);
+        assert_eq!(provenance(&wallet_list, 1).rounding_adjustment, 0.0);
+    }
+
+    #[test]
+    fn rounds_down_and_records_the_adjustment() {
+        let wallet_list = allocate(snapshot(&[1, 1, 1]), &HashMap::new(), 10, None, None).unwrap();
+        assert_eq!(amounts(&wallet_list), vec![3, 3, 3]);
+        for i in 0..3 {
+            let provenance = provenance(&wallet_list, i);
+            assert!((provenance.rounding_adjustment + 1.0 / 3.0).abs() < 1e-12);
+            assert_eq!(provenance.bound_applied, BoundApplied::None);
+        }
+    }
+
+    #[test]
+    fn applies_cap_and_floor_and_skips_wallets_without_weight() {
+        let multipliers = HashMap::from([(Pubkey::new_from_array([3; 32]), 0.0)]);
+        let wallet_list = allocate(
+            snapshot(&[1, 9, 5, 0]),
+            &multipliers,
+            150,
+            Some(80),
+            Some(20),
+        )
+        .unwrap();
+        // 150 * 1/10 = 15 -> floor, 150 * 9/10 = 135 -> cap, multiplier 0 and balance 0 are left out
+        assert_eq!(amounts(&wallet_list), vec![20, 80]);
+        assert_eq!(
+            provenance(&wallet_list, 0).bound_applied,
+            BoundApplied::Floor
+        );
+        assert_eq!(provenance(&wallet_list, 1).bound_applied, BoundApplied::Cap);
+    }
+
+    #[test]
+    fn rejects_allocations_over_the_airdrop_amount() {
+        let err = allocate(snapshot(&[1, 9]), &HashMap::new(), 100, None, Some(15)).unwrap_err();
+        assert!(matches!(err, Error::AllocationError(_)));
+        let err =
+            allocate(snapshot(&[1, 9]), &HashMap::new(), 100, Some(10), Some(20)).unwrap_err();
+        assert!(matches!(err, Error::AllocationError(_)));
+        let err = allocate(snapshot(&[0, 0]), &HashMap::new(), 100, None, None).unwrap_err();
+        assert!(matches!(err, Error::AllocationError(_)));
+    }
+}
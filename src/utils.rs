@@ -1,60 +1,501 @@
 use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
     io::Write,
     path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
 };
 
+use sanctum_solana_cli_utils::ConfigWrapper;
 use serde_json::json;
-use solana_account_decoder::parse_token::{parse_token, TokenAccountType};
-use solana_client::rpc_client::RpcClient;
-use solana_program::instruction::Instruction;
-use solana_rpc_client_api::{request::RpcRequest, response::RpcResult};
+use solana_account_decoder::{
+    parse_token::{parse_token, TokenAccountType, UiAccountState},
+    UiAccountData,
+};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_program::{
+    clock::Slot, instruction::Instruction, program_error::ProgramError, program_pack::Pack,
+};
+use solana_rpc_client_api::{
+    config::RpcBlockConfig,
+    request::{RpcRequest, TokenAccountsFilter},
+    response::{RpcKeyedAccount, RpcResult},
+};
 use solana_sdk::{
     account::Account,
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::{LOOKUP_TABLE_MAX_ADDRESSES, LOOKUP_TABLE_META_SIZE},
+        AddressLookupTableAccount,
+    },
     commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
     message::{v0::Message, VersionedMessage},
+    nonce::state::{State as NonceState, Versions as NonceVersions},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
-    signature::Signature,
+    signature::{Keypair, Signature},
     signer::Signer,
+    system_instruction::{advance_nonce_account, create_nonce_account},
     transaction::VersionedTransaction,
 };
-use solana_transaction_status::TransactionStatus;
-use spl_token_2022::{extension::StateWithExtensionsOwned, state::Mint};
+use solana_transaction_status::{TransactionDetails, TransactionStatus};
+use spl_token_2022::{
+    extension::{
+        default_account_state::DefaultAccountState,
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        transfer_hook::TransferHook,
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions, StateWithExtensionsOwned,
+    },
+    offchain::{add_extra_account_metas, AccountFetchError},
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use spl_token_metadata_interface::state::TokenMetadata;
 
-use crate::{data::Status, errors::Result};
+use crate::{
+    consts::{
+        CONFIDENTIAL_MINT_BURN_EXTENSION_TYPE, CREATE_ATA_CU_ESTIMATE, LAMPORTS_PER_SIGNATURE,
+        LOOKUP_TABLE_EXTEND_CHUNK_SIZE, LOOKUP_TABLE_POLL_INTERVAL_MILLIS, MEMO_CU_ESTIMATE,
+        METAPLEX_TOKEN_METADATA_PROGRAM_ID, MICRO_LAMPORTS_PER_LAMPORT, NONCE_MAX_POLLS,
+        NONCE_POLL_INTERVAL_MILLIS, PAUSABLE_ACCOUNT_EXTENSION_TYPE, PAUSABLE_CONFIG_PAUSED_OFFSET,
+        PAUSABLE_EXTENSION_TYPE, PRIORITIZATION_FEE_MAX_ACCOUNTS, RAFFLE_SLOT_POLL_INTERVAL_SECS,
+        SCALED_UI_AMOUNT_EXTENSION_TYPE, SEND_BACKOFF_BASE_MILLIS, SEND_MAX_BACKOFF_MILLIS,
+        SEND_MAX_RETRY, TLV_HEADER_LEN, TOKEN_ACCOUNT_DISCOVERY_CONCURRENCY, TRANSFER_CU_ESTIMATE,
+        TRANSFER_HOOK_CU_ESTIMATE,
+    },
+    data::{ClusterProfile, DisqualificationReason, NonceAccountList, QualificationRules, Status},
+    errors::{Error, Result},
+};
 
 // check if given token_account is qualified for airdrop
 // returns Qualified | Disqualified
-fn qualification_predicate(token_account: &Option<Account>, token_decimals: u8) -> Status {
+// NOTE: regardless of the rules, the token account has to be of the airdrop mint and owned by the wallet
+//       (the ata column might have been given by the wallet list), and not frozen since a transfer into it
+//       always fails (along with every other transfer packed into the same tx)
+#[allow(clippy::too_many_arguments)]
+fn qualification_predicate(
+    wallet_pubkey: &Pubkey,
+    token_account: &Option<Account>,
+    wallet_account: Option<&Account>,
+    can_create_token_account: bool,
+    token_mint_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+    token_decimals: u8,
+    rules: &QualificationRules,
+) -> Status {
     match token_account {
+        Some(account) if account.owner != *token_program_id => {
+            Status::Disqualified(DisqualificationReason::NotTokenAccount)
+        }
         Some(account) => {
             let token_account = parse_token(&account.data, Some(token_decimals));
             match token_account {
-                Ok(TokenAccountType::Account(_ui_token_account)) => {
-                    // NB: check additional qualifications
-                    // NOTE: for now just having an account qualifies for airdrop
-                    Status::Qualified
+                Ok(TokenAccountType::Account(ui_token_account))
+                    if ui_token_account.mint != token_mint_pubkey.to_string() =>
+                {
+                    Status::Disqualified(DisqualificationReason::MintMismatch)
+                }
+                Ok(TokenAccountType::Account(ui_token_account))
+                    if ui_token_account.owner != wallet_pubkey.to_string() =>
+                {
+                    Status::Disqualified(DisqualificationReason::OwnerMismatch)
+                }
+                Ok(TokenAccountType::Account(ui_token_account))
+                    if matches!(ui_token_account.state, UiAccountState::Frozen) =>
+                {
+                    Status::Disqualified(DisqualificationReason::Frozen)
                 }
-                _ => Status::Disqualified,
+                Ok(TokenAccountType::Account(ui_token_account)) => {
+                    match rules.evaluate(&ui_token_account, wallet_account) {
+                        Ok(()) => Status::Qualified,
+                        Err(reason) => Status::Disqualified(reason),
+                    }
+                }
+                _ => Status::Disqualified(DisqualificationReason::NotTokenAccount),
+            }
+        }
+        None if can_create_token_account => {
+            match rules.evaluate_missing_token_account(wallet_account) {
+                Ok(()) => Status::Qualified,
+                Err(reason) => Status::Disqualified(reason),
+            }
+        }
+        None => Status::Disqualified(DisqualificationReason::NoAccount),
+    }
+}
+
+/// Result of checking a single destination token account
+#[derive(Debug, Clone)]
+pub struct AtaCheck {
+    pub status: Status,
+    pub exists: bool,
+    /// MemoTransfer extension requires incoming transfers to be preceded by a memo ix
+    pub memo_required: bool,
+    pub extension_types: Vec<String>,
+}
+
+impl AtaCheck {
+    fn failed(err: &impl ToString) -> Self {
+        Self {
+            status: Status::Failed(err.to_string()),
+            exists: false,
+            memo_required: false,
+            extension_types: vec![],
+        }
+    }
+}
+
+// returns (extension names, whether memo is required for incoming transfers) of given token account
+fn get_token_account_extensions(account: &Account) -> (Vec<String>, bool) {
+    let Ok(state) = StateWithExtensions::<TokenAccount>::unpack(&account.data) else {
+        return (vec![], false);
+    };
+    let mut memo_required = false;
+    let extension_names = get_tlv_entries(state.get_tlv_data())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(extension_type, value)| {
+            if extension_type == ExtensionType::MemoTransfer as u16 {
+                // NOTE: MemoTransfer is a single bool (require_incoming_transfer_memos)
+                memo_required = value.first().is_some_and(|flag| *flag != 0);
             }
+            get_extension_name(extension_type)
+        })
+        .collect();
+    (extension_names, memo_required)
+}
+
+/// Returns the (extension type, value) of every TLV entry of given extension data.
+/// Entries are read by type number, so that extensions unknown to the bundled spl-token-2022
+/// (e.g. pausable) do not fail the whole parse like BaseStateWithExtensions does
+fn get_tlv_entries(tlv_data: &[u8]) -> Result<Vec<(u16, &[u8])>> {
+    let mut entries = Vec::new();
+    let mut start = 0;
+    // NOTE: type (u16 le) | length (u16 le) | value, an uninitialized type ends the entries
+    while let Some(header) = tlv_data.get(start..start + TLV_HEADER_LEN) {
+        let extension_type = u16::from_le_bytes([header[0], header[1]]);
+        if extension_type == ExtensionType::Uninitialized as u16 {
+            break;
         }
-        None => Status::Disqualified,
+        let value_start = start + TLV_HEADER_LEN;
+        let value_end = value_start + u16::from_le_bytes([header[2], header[3]]) as usize;
+        let value = tlv_data
+            .get(value_start..value_end)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        entries.push((extension_type, value));
+        start = value_end;
+    }
+    Ok(entries)
+}
+
+fn get_extension_name(extension_type: u16) -> String {
+    match ExtensionType::try_from(extension_type) {
+        Ok(known_extension_type) => format!("{known_extension_type:?}"),
+        Err(_) => match extension_type {
+            CONFIDENTIAL_MINT_BURN_EXTENSION_TYPE => "ConfidentialMintBurn".to_string(),
+            SCALED_UI_AMOUNT_EXTENSION_TYPE => "ScaledUiAmount".to_string(),
+            PAUSABLE_EXTENSION_TYPE => "Pausable".to_string(),
+            PAUSABLE_ACCOUNT_EXTENSION_TYPE => "PausableAccount".to_string(),
+            _ => format!("Unknown({extension_type})"),
+        },
     }
 }
 
 // Failed | Qualified | Disqualified
-pub fn check_atas(rpc_client: &RpcClient, atas: &[Pubkey], token_decimals: u8) -> Vec<Status> {
+#[allow(clippy::too_many_arguments)]
+pub fn check_atas(
+    rpc_client: &RpcClient,
+    wallets: &[Pubkey],
+    atas: &[Pubkey],
+    can_create_atas: &[bool],
+    token_mint_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+    token_decimals: u8,
+    rules: &QualificationRules,
+) -> Vec<AtaCheck> {
+    let wallet_accounts = if rules.requires_wallet_account() || can_create_atas.contains(&true) {
+        match rpc_client.get_multiple_accounts_with_commitment(wallets, rpc_client.commitment()) {
+            Ok(response) => response.value,
+            Err(err) => return vec![AtaCheck::failed(&err); atas.len()],
+        }
+    } else {
+        vec![None; wallets.len()]
+    };
     let res = rpc_client.get_multiple_accounts_with_commitment(atas, rpc_client.commitment());
     match res {
         Ok(response) => response
             .value
             .iter()
-            .map(|token_account| qualification_predicate(token_account, token_decimals))
+            .zip(wallets.iter().zip(wallet_accounts.iter()))
+            .zip(can_create_atas.iter())
+            .map(
+                |((token_account, (wallet_pubkey, wallet_account)), can_create_ata)| {
+                    let status = qualification_predicate(
+                        wallet_pubkey,
+                        token_account,
+                        wallet_account.as_ref(),
+                        *can_create_ata,
+                        token_mint_pubkey,
+                        token_program_id,
+                        token_decimals,
+                        rules,
+                    );
+                    let (extension_types, memo_required) = token_account
+                        .as_ref()
+                        .map(get_token_account_extensions)
+                        .unwrap_or_default();
+                    AtaCheck {
+                        status,
+                        exists: token_account.is_some(),
+                        memo_required,
+                        extension_types,
+                    }
+                },
+            )
             .collect(),
-        Err(err) => vec![Status::Failed(err.to_string()); atas.len()],
+        Err(err) => vec![AtaCheck::failed(&err); atas.len()],
+    }
+}
+
+/// Returns the token account of given mint owned by given wallet with the largest balance,
+/// excluding frozen accounts (used when the wallet has no ata)
+fn find_largest_token_account(
+    rpc_client: &RpcClient,
+    wallet_pubkey: &Pubkey,
+    token_mint_pubkey: &Pubkey,
+) -> Result<Option<Pubkey>> {
+    let largest = rpc_client
+        .get_token_accounts_by_owner(wallet_pubkey, TokenAccountsFilter::Mint(*token_mint_pubkey))?
+        .into_iter()
+        .filter_map(|RpcKeyedAccount { pubkey, account }| {
+            let UiAccountData::Json(parsed_account) = account.data else {
+                return None;
+            };
+            match serde_json::from_value(parsed_account.parsed) {
+                Ok(TokenAccountType::Account(ui_token_account))
+                    if !matches!(ui_token_account.state, UiAccountState::Frozen)
+                        && ui_token_account.owner == wallet_pubkey.to_string() =>
+                {
+                    let pubkey = Pubkey::from_str(&pubkey).ok()?;
+                    let balance: u64 = ui_token_account.token_amount.amount.parse().ok()?;
+                    Some((pubkey, balance))
+                }
+                _ => None,
+            }
+        })
+        .max_by_key(|(_pubkey, balance)| *balance)
+        .map(|(pubkey, _balance)| pubkey);
+    Ok(largest)
+}
+
+/// Runs find_largest_token_account for every given wallet on up to
+/// TOKEN_ACCOUNT_DISCOVERY_CONCURRENCY threads, results are in the order of given wallets
+pub fn find_largest_token_accounts(
+    rpc_client: &RpcClient,
+    wallet_pubkeys: &[Pubkey],
+    token_mint_pubkey: &Pubkey,
+) -> Vec<Result<Option<Pubkey>>> {
+    if wallet_pubkeys.is_empty() {
+        return vec![];
+    }
+    let chunk_size = wallet_pubkeys
+        .len()
+        .div_ceil(TOKEN_ACCOUNT_DISCOVERY_CONCURRENCY);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = wallet_pubkeys
+            .chunks(chunk_size)
+            .map(|wallet_pubkeys| {
+                scope.spawn(move || {
+                    wallet_pubkeys
+                        .iter()
+                        .map(|wallet_pubkey| {
+                            find_largest_token_account(rpc_client, wallet_pubkey, token_mint_pubkey)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            // UNWRAP-SAFETY: lookups do not panic
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Returns the rent exempt lamports of a token account (i.e. an ata to be created) of given mint
+pub fn get_token_account_rent(
+    rpc_client: &RpcClient,
+    token_mint_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<u64> {
+    let account_len = if *token_program_id == spl_token_2022::id() {
+        let Account { data, .. } = rpc_client.get_account(token_mint_pubkey)?;
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        let tlv_entries = get_tlv_entries(mint.get_tlv_data())?;
+        let mint_extension_types: Vec<ExtensionType> = tlv_entries
+            .iter()
+            .filter_map(|(extension_type, _value)| ExtensionType::try_from(*extension_type).ok())
+            .collect();
+        let mut extension_types =
+            ExtensionType::get_required_init_account_extensions(&mint_extension_types);
+        // NOTE: atas of token-2022 are always initialized with immutable owner
+        extension_types.push(ExtensionType::ImmutableOwner);
+        let account_len =
+            ExtensionType::try_calculate_account_len::<TokenAccount>(&extension_types)?;
+        // NOTE: atas of a pausable mint also carry the (empty) pausable account extension,
+        //  which is unknown to the bundled spl-token-2022
+        match tlv_entries
+            .iter()
+            .any(|(extension_type, _value)| *extension_type == PAUSABLE_EXTENSION_TYPE)
+        {
+            true => account_len + TLV_HEADER_LEN,
+            false => account_len,
+        }
+    } else {
+        TokenAccount::LEN
+    };
+    Ok(rpc_client.get_minimum_balance_for_rent_exemption(account_len)?)
+}
+
+/// resolves the extra accounts required by the transfer hook program of a mint.
+/// fetched account data is cached since most of it (mint, validation account) is shared by every transfer
+pub struct TransferHookResolver<'a> {
+    rpc_client: &'a RpcClient,
+    account_data_cache: RefCell<HashMap<Pubkey, Option<Vec<u8>>>>,
+}
+
+impl<'a> TransferHookResolver<'a> {
+    pub fn new(rpc_client: &'a RpcClient) -> Self {
+        Self {
+            rpc_client,
+            account_data_cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn get_account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>> {
+        if let Some(data) = self.account_data_cache.borrow().get(pubkey) {
+            return Ok(data.clone());
+        }
+        let data = self
+            .rpc_client
+            .get_account_with_commitment(pubkey, self.rpc_client.commitment())?
+            .value
+            .map(|account| account.data);
+        self.account_data_cache
+            .borrow_mut()
+            .insert(*pubkey, data.clone());
+        Ok(data)
+    }
+
+    pub fn add_extra_account_metas(
+        &self,
+        transfer_ix: &mut Instruction,
+        source: &Pubkey,
+        token_mint_pubkey: &Pubkey,
+        destination: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        // NOTE: account data is fetched synchronously, the futures are always ready
+        futures::executor::block_on(add_extra_account_metas(
+            transfer_ix,
+            source,
+            token_mint_pubkey,
+            destination,
+            authority,
+            amount,
+            |pubkey| {
+                std::future::ready(
+                    self.get_account_data(&pubkey)
+                        .map_err(|err| -> AccountFetchError { err.to_string().into() }),
+                )
+            },
+        ))
+        .map_err(|err| Error::TransferHookError(err.to_string()))
     }
 }
 
+// rough upper bound of compute units used by an ix
+fn estimate_compute_units(ix: &Instruction) -> u32 {
+    if ix.program_id == spl_associated_token_account::id() {
+        CREATE_ATA_CU_ESTIMATE
+    } else if ix.program_id == spl_memo::id() {
+        MEMO_CU_ESTIMATE
+    } else if ix.accounts.len() > 4 {
+        // NOTE: transfer_checked (with fee) has 4 accounts, the rest are transfer hook extra accounts
+        TRANSFER_CU_ESTIMATE + TRANSFER_HOOK_CU_ESTIMATE
+    } else {
+        TRANSFER_CU_ESTIMATE
+    }
+}
+
+// size of a tx signed by the payer only, containing compute budget ixs and given ixs
+fn serialized_tx_size(
+    payer_pubkey: &Pubkey,
+    ixs: &[Instruction],
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Option<usize> {
+    let message = Message::try_compile(
+        payer_pubkey,
+        ixs,
+        address_lookup_table_accounts,
+        Hash::default(),
+    )
+    .ok()?;
+    let num_signatures = message.header.num_required_signatures as usize;
+    // NOTE: signatures are prefixed with their count as compact-u16 (1 byte for a single signature)
+    Some(1 + num_signatures * 64 + VersionedMessage::V0(message).serialize().len())
+}
+
+/// greedily packs items into chunks, each sent as a single tx.
+/// an item is added as long as the tx stays within the packet size and the estimated compute units within compute_unit_limit.
+/// NOTE: an item that does not fit into a tx on its own still gets its own chunk
+pub fn pack_ixs<T>(
+    payer_pubkey: &Pubkey,
+    items_with_ixs: Vec<(T, Vec<Instruction>)>,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+    compute_unit_limit: u32,
+    with_advance_nonce: bool,
+) -> Vec<Vec<(T, Vec<Instruction>)>> {
+    // NOTE: placeholder nonce account, only its size matters
+    let advance_nonce_ix =
+        with_advance_nonce.then(|| advance_nonce_account(&Pubkey::new_unique(), payer_pubkey));
+    let compute_budget_ixs: Vec<Instruction> = advance_nonce_ix
+        .into_iter()
+        .chain(get_compute_budget_ixs(compute_unit_limit, 0))
+        .collect();
+    let mut chunks = Vec::new();
+    let mut chunk: Vec<(T, Vec<Instruction>)> = Vec::new();
+    let mut chunk_ixs = compute_budget_ixs.to_vec();
+    let mut chunk_compute_units = 0;
+    for (item, ixs) in items_with_ixs {
+        let item_compute_units: u32 = ixs.iter().map(estimate_compute_units).sum();
+        let candidate_ixs: Vec<Instruction> = chunk_ixs.iter().chain(ixs.iter()).cloned().collect();
+        let fits = chunk_compute_units + item_compute_units <= compute_unit_limit
+            && serialized_tx_size(payer_pubkey, &candidate_ixs, address_lookup_table_accounts)
+                .is_some_and(|size| size <= PACKET_DATA_SIZE);
+        if !fits && !chunk.is_empty() {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_ixs = compute_budget_ixs.to_vec();
+            chunk_compute_units = 0;
+        }
+        chunk_ixs.extend(ixs.iter().cloned());
+        chunk_compute_units += item_compute_units;
+        chunk.push((item, ixs));
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
 /// Returns (token_program_id: Pubkey, decimals: u8)
 pub fn get_token_mint_info(
     rpc_client: &RpcClient,
@@ -69,21 +510,595 @@ pub fn get_token_mint_info(
     Ok((token_program_id, token_decimals))
 }
 
-/// prepare transaction with given ixs
+#[derive(Debug)]
+pub struct MintMetadata {
+    pub name: String,
+    pub symbol: String,
+}
+
+/// name and symbol of a mint, read from the token-2022 metadata extension or else the metaplex metadata account.
+/// only used for display, so any failure just returns None
+pub fn get_mint_metadata(
+    rpc_client: &RpcClient,
+    token_mint_pubkey: &Pubkey,
+) -> Option<MintMetadata> {
+    let Account { data, .. } = rpc_client.get_account(token_mint_pubkey).ok()?;
+    if let Ok(TokenMetadata { name, symbol, .. }) = StateWithExtensions::<Mint>::unpack(&data)
+        .and_then(|mint| mint.get_variable_len_extension::<TokenMetadata>())
+    {
+        return Some(MintMetadata { name, symbol });
+    }
+
+    let (metadata_pubkey, _bump) = Pubkey::find_program_address(
+        &[
+            b"metadata",
+            METAPLEX_TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            token_mint_pubkey.as_ref(),
+        ],
+        &METAPLEX_TOKEN_METADATA_PROGRAM_ID,
+    );
+    let Account { data, .. } = rpc_client.get_account(&metadata_pubkey).ok()?;
+    // layout: key (1), update authority (32), mint (32), name (borsh string), symbol (borsh string)
+    let mut offset = 65;
+    let mut read_string = || -> Option<String> {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let value = String::from_utf8_lossy(data.get(offset + 4..offset + 4 + len)?)
+            .trim_end_matches('\0')
+            .to_owned();
+        offset += 4 + len;
+        Some(value)
+    };
+    let name = read_string()?;
+    let symbol = read_string()?;
+    Some(MintMetadata { name, symbol })
+}
+
+/// Token-2022 extensions of a mint that affect check and send
+#[derive(Debug, Default, Clone)]
+pub struct MintExtensions {
+    /// names of the mint extensions, including ones unknown to the bundled spl-token-2022
+    pub extension_types: Vec<String>,
+    /// nothing can be transferred
+    pub non_transferable: bool,
+    /// newly created token accounts are frozen (i.e. atas to be created cannot receive)
+    pub default_frozen: bool,
+    /// transfers are charged a fee withheld in the destination account
+    pub transfer_fee_config: Option<TransferFeeConfig>,
+    /// every transfer has to carry the extra accounts required by the hook program
+    pub transfer_hook_program_id: Option<Pubkey>,
+    /// transfers fail while the pause authority has the mint paused
+    pub pausable: bool,
+}
+
+/// Refuses paused mints, since no transfer can succeed until the mint is resumed
+// NOTE: extensions are read from the raw TLV entries, so that mints with extensions unknown to the
+//  bundled spl-token-2022 (e.g. pausable) can still be inspected
+pub fn get_mint_extensions(
+    rpc_client: &RpcClient,
+    token_mint_pubkey: &Pubkey,
+) -> Result<MintExtensions> {
+    let Account { data, .. } = rpc_client.get_account(token_mint_pubkey)?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let mut mint_extensions = MintExtensions::default();
+    let mut paused = false;
+    for (extension_type, value) in get_tlv_entries(mint.get_tlv_data())? {
+        match ExtensionType::try_from(extension_type) {
+            Ok(ExtensionType::NonTransferable) => mint_extensions.non_transferable = true,
+            Ok(ExtensionType::DefaultAccountState) => {
+                mint_extensions.default_frozen = bytemuck::try_from_bytes::<DefaultAccountState>(
+                    value,
+                )
+                .is_ok_and(|default_account_state| {
+                    default_account_state.state == AccountState::Frozen as u8
+                });
+            }
+            Ok(ExtensionType::TransferFeeConfig) => {
+                mint_extensions.transfer_fee_config =
+                    bytemuck::try_from_bytes::<TransferFeeConfig>(value)
+                        .ok()
+                        .copied();
+            }
+            Ok(ExtensionType::TransferHook) => {
+                mint_extensions.transfer_hook_program_id =
+                    bytemuck::try_from_bytes::<TransferHook>(value)
+                        .ok()
+                        .and_then(|transfer_hook| Option::<Pubkey>::from(transfer_hook.program_id));
+            }
+            Err(_) if extension_type == PAUSABLE_EXTENSION_TYPE => {
+                mint_extensions.pausable = true;
+                paused = value
+                    .get(PAUSABLE_CONFIG_PAUSED_OFFSET)
+                    .is_some_and(|flag| *flag != 0);
+            }
+            _ => {}
+        }
+        mint_extensions
+            .extension_types
+            .push(get_extension_name(extension_type));
+    }
+    if paused {
+        return Err(Error::MintPaused(token_mint_pubkey.to_string()));
+    }
+    if mint_extensions.pausable {
+        log::warn!("Mint is pausable, its transfers fail while it is paused");
+    }
+    Ok(mint_extensions)
+}
+
+/// rpc client of the selected cluster profile, or else of the solana CLI config
+pub fn get_rpc_client(
+    config: &ConfigWrapper,
+    cluster_profile: Option<&ClusterProfile>,
+) -> RpcClient {
+    match cluster_profile {
+        Some(cluster_profile) => RpcClient::new_with_commitment(
+            cluster_profile.rpc_url.clone(),
+            cluster_profile.commitment_config(),
+        ),
+        None => config.rpc_client(),
+    }
+}
+
+/// prepare transaction with given ixs, returns (tx, last block height its blockhash is valid for)
 pub fn prep_tx(
     rpc_client: &RpcClient,
     payer: &dyn Signer,
     ixs: &[Instruction],
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<(VersionedTransaction, u64)> {
+    let (rbh, last_valid_block_height) =
+        rpc_client.get_latest_blockhash_with_commitment(CommitmentConfig::finalized())?;
+    let tx = prep_tx_with_blockhash(payer, ixs, address_lookup_table_accounts, rbh)?;
+    Ok((tx, last_valid_block_height))
+}
+
+/// prepare transaction with given ixs and blockhash (e.g. a durable nonce)
+pub fn prep_tx_with_blockhash(
+    payer: &dyn Signer,
+    ixs: &[Instruction],
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+    blockhash: Hash,
 ) -> Result<VersionedTransaction> {
-    let rbh = rpc_client
-        .get_latest_blockhash_with_commitment(CommitmentConfig::finalized())?
-        .0;
     Ok(VersionedTransaction::try_new(
-        VersionedMessage::V0(Message::try_compile(&payer.pubkey(), ixs, &[], rbh).unwrap()),
+        VersionedMessage::V0(
+            Message::try_compile(
+                &payer.pubkey(),
+                ixs,
+                address_lookup_table_accounts,
+                blockhash,
+            )
+            .unwrap(),
+        ),
         &[payer],
     )?)
 }
 
+/// How send submits txs
+#[derive(Debug, Clone, Copy)]
+pub struct SendPipeline {
+    /// number of txs submitted in parallel
+    pub concurrency: usize,
+    /// max txs dispatched per second (None for no limit)
+    pub max_tps: Option<u32>,
+    pub blockhash_refresh_interval: Duration,
+}
+
+/// How send builds and submits the txs of an airdrop
+#[derive(Debug, Clone, Copy)]
+pub struct SendOptions<'a> {
+    pub address_lookup_table_accounts: &'a [AddressLookupTableAccount],
+    /// also the cap of simulated compute unit limits
+    pub compute_unit_limit: u32,
+    /// fixed compute unit price (micro lamports), unless priority_fee_policy is set
+    pub compute_unit_price: u64,
+    pub priority_fee_policy: Option<PriorityFeePolicy>,
+    /// if set, compute unit limits are derived from simulation with this margin
+    pub compute_unit_margin_percent: Option<u32>,
+    pub pipeline: SendPipeline,
+    pub dry_run: bool,
+    pub should_confirm: bool,
+    /// epoch from which the transfer fee the transfer ixs were built with no longer applies
+    pub transfer_fee_change_epoch: Option<u64>,
+    /// token account the airdrop is sent from
+    pub source_ata: Pubkey,
+}
+
+/// Latest (finalized) blockhash shared by the txs built within refresh_interval
+#[derive(Debug)]
+pub struct BlockhashCache {
+    refresh_interval: Duration,
+    cached: Option<(Hash, u64, Instant)>,
+}
+
+impl BlockhashCache {
+    pub fn new(refresh_interval: Duration) -> Self {
+        Self {
+            refresh_interval,
+            cached: None,
+        }
+    }
+
+    /// (blockhash, last valid block height), refreshed once older than refresh_interval
+    pub fn get(&mut self, rpc_client: &RpcClient) -> Result<(Hash, u64)> {
+        match self.cached {
+            Some((blockhash, last_valid_block_height, fetched_at))
+                if fetched_at.elapsed() < self.refresh_interval =>
+            {
+                Ok((blockhash, last_valid_block_height))
+            }
+            _ => {
+                let (blockhash, last_valid_block_height) = rpc_client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::finalized())?;
+                self.cached = Some((blockhash, last_valid_block_height, Instant::now()));
+                Ok((blockhash, last_valid_block_height))
+            }
+        }
+    }
+}
+
+/// Current epoch shared by the txs built within refresh_interval
+#[derive(Debug)]
+pub struct EpochCache {
+    refresh_interval: Duration,
+    cached: Option<(u64, Instant)>,
+}
+
+impl EpochCache {
+    pub fn new(refresh_interval: Duration) -> Self {
+        Self {
+            refresh_interval,
+            cached: None,
+        }
+    }
+
+    /// current epoch, refreshed once older than refresh_interval
+    pub fn get(&mut self, rpc_client: &RpcClient) -> Result<u64> {
+        match self.cached {
+            Some((epoch, fetched_at)) if fetched_at.elapsed() < self.refresh_interval => Ok(epoch),
+            _ => {
+                let epoch = rpc_client.get_epoch_info()?.epoch;
+                self.cached = Some((epoch, Instant::now()));
+                Ok(epoch)
+            }
+        }
+    }
+}
+
+/// Returns (current epoch, transfer fee of the current epoch, epoch from which the fee differs)
+// NOTE: a transfer fee config only holds the current and the newer (scheduled) fee,
+//  so within a send the fee changes at most once
+pub fn get_epoch_transfer_fee(
+    rpc_client: &RpcClient,
+    transfer_fee_config: &TransferFeeConfig,
+) -> Result<(u64, TransferFee, Option<u64>)> {
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let transfer_fee = *transfer_fee_config.get_epoch_fee(epoch);
+    let newer_transfer_fee = transfer_fee_config.newer_transfer_fee;
+    let newer_epoch = u64::from(newer_transfer_fee.epoch);
+    let change_epoch = (newer_epoch > epoch
+        && (newer_transfer_fee.transfer_fee_basis_points
+            != transfer_fee.transfer_fee_basis_points
+            || newer_transfer_fee.maximum_fee != transfer_fee.maximum_fee))
+        .then_some(newer_epoch);
+    Ok((epoch, transfer_fee, change_epoch))
+}
+
+/// exponential backoff after given number of consecutive send errors
+pub fn get_send_backoff(n_errors: u32) -> Duration {
+    let millis = SEND_BACKOFF_BASE_MILLIS.saturating_mul(1 << n_errors.min(16));
+    Duration::from_millis(millis.min(SEND_MAX_BACKOFF_MILLIS))
+}
+
+/// Submits given tx, retrying with backoff on rpc errors.
+/// consecutive_errors is shared by every worker so that the dispatcher backs off too
+pub fn submit_tx(
+    rpc_client: &RpcClient,
+    tx: &VersionedTransaction,
+    should_confirm: bool,
+    consecutive_errors: &AtomicU32,
+) {
+    let sig = tx.signatures[0];
+    for attempt in 1..=SEND_MAX_RETRY {
+        let res = match should_confirm {
+            true => rpc_client.send_and_confirm_transaction(tx).map(|_| ()),
+            false => rpc_client.send_transaction(tx).map(|_| ()),
+        };
+        match res {
+            Ok(()) => {
+                consecutive_errors.store(0, Ordering::Relaxed);
+                return;
+            }
+            Err(err) => {
+                let n_errors = consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
+                if attempt == SEND_MAX_RETRY {
+                    log::warn!("Failed to send tx {sig}, keeping it for rebroadcast: {err}");
+                    return;
+                }
+                log::debug!("Failed to send tx {sig} (attempt {attempt}): {err}");
+                std::thread::sleep(get_send_backoff(n_errors));
+            }
+        }
+    }
+}
+
+/// durable nonce stored in given nonce account
+pub fn get_durable_nonce(
+    rpc_client: &RpcClient,
+    nonce_pubkey: &Pubkey,
+    commitment_config: CommitmentConfig,
+) -> Result<Hash> {
+    let account = rpc_client
+        .get_account_with_commitment(nonce_pubkey, commitment_config)?
+        .value
+        .ok_or_else(|| Error::NonceError(format!("{nonce_pubkey}: Account not found")))?;
+    parse_durable_nonce(&account.data).ok_or_else(|| {
+        Error::NonceError(format!("{nonce_pubkey}: Not an initialized nonce account"))
+    })
+}
+
+fn parse_durable_nonce(data: &[u8]) -> Option<Hash> {
+    match bincode::deserialize::<NonceVersions>(data).ok()?.state() {
+        NonceState::Initialized(data) => Some(data.blockhash()),
+        NonceState::Uninitialized => None,
+    }
+}
+
+/// Nonce accounts of the pool, shared by the workers preparing txs
+// NOTE: an acquired account stays reserved until its tx is recorded as sent (or for the rest of the
+// send if it never is), so that two txs are never built on the same nonce
+#[derive(Debug)]
+pub struct NoncePool {
+    /// (nonce account, last used nonce)
+    entries: Vec<(Pubkey, Option<Hash>)>,
+    reserved: HashSet<Pubkey>,
+}
+
+impl NoncePool {
+    pub fn new(nonce_account_list: &NonceAccountList) -> Result<Self> {
+        Ok(Self {
+            entries: nonce_account_list.entries()?,
+            reserved: HashSet::new(),
+        })
+    }
+
+    pub fn set_last_used_nonce(&mut self, nonce_pubkey: &Pubkey, nonce: &Hash) {
+        if let Some((_, last_used_nonce)) = self
+            .entries
+            .iter_mut()
+            .find(|(pubkey, _)| pubkey == nonce_pubkey)
+        {
+            *last_used_nonce = Some(*nonce);
+        }
+        self.reserved.remove(nonce_pubkey);
+    }
+}
+
+/// Reserves the first nonce account whose nonce advanced past its last used nonce (i.e. its last tx landed)
+/// and returns it with its current nonce, polling until one frees up (None if none did)
+// NOTE: the pool is not locked while polling, so that other workers can record their txs meanwhile
+pub fn acquire_nonce_account(
+    rpc_client: &RpcClient,
+    nonce_pool: &Mutex<NoncePool>,
+) -> Result<Option<(Pubkey, Hash)>> {
+    let pubkeys: Vec<Pubkey> = nonce_pool
+        .lock()
+        .unwrap()
+        .entries
+        .iter()
+        .map(|(pubkey, _)| *pubkey)
+        .collect();
+    for _ in 0..NONCE_MAX_POLLS {
+        if is_interrupted() {
+            return Ok(None);
+        }
+        let accounts = rpc_client.get_multiple_accounts(&pubkeys)?;
+        {
+            let mut nonce_pool = nonce_pool.lock().unwrap();
+            let NoncePool { entries, reserved } = &mut *nonce_pool;
+            for ((nonce_pubkey, last_used_nonce), account) in entries.iter().zip(accounts) {
+                if reserved.contains(nonce_pubkey) {
+                    continue;
+                }
+                let Some(nonce) = account.and_then(|account| parse_durable_nonce(&account.data))
+                else {
+                    log::warn!("Skipping {nonce_pubkey}: Not an initialized nonce account");
+                    continue;
+                };
+                if last_used_nonce.as_ref() != Some(&nonce) {
+                    reserved.insert(*nonce_pubkey);
+                    return Ok(Some((*nonce_pubkey, nonce)));
+                }
+            }
+        }
+        log::debug!("Waiting for a nonce account to free up ...");
+        std::thread::sleep(Duration::from_millis(NONCE_POLL_INTERVAL_MILLIS));
+    }
+    Ok(None)
+}
+
+pub fn get_nonce_account_rent(rpc_client: &RpcClient) -> Result<u64> {
+    Ok(rpc_client.get_minimum_balance_for_rent_exemption(NonceState::size())?)
+}
+
+/// Creates given number of nonce accounts (authority: payer)
+pub fn create_nonce_accounts(
+    rpc_client: &RpcClient,
+    payer: &dyn Signer,
+    n: usize,
+) -> Result<Vec<Pubkey>> {
+    let rent = get_nonce_account_rent(rpc_client)?;
+    let mut nonce_pubkeys = Vec::new();
+    for _ in 0..n {
+        let nonce_keypair = Keypair::new();
+        log::info!("Creating nonce account {} ...", nonce_keypair.pubkey());
+        let ixs = create_nonce_account(
+            &payer.pubkey(),
+            &nonce_keypair.pubkey(),
+            &payer.pubkey(),
+            rent,
+        );
+        let rbh = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::finalized())?
+            .0;
+        let tx = VersionedTransaction::try_new(
+            VersionedMessage::V0(Message::try_compile(&payer.pubkey(), &ixs, &[], rbh).unwrap()),
+            &[payer, &nonce_keypair],
+        )?;
+        rpc_client.send_and_confirm_transaction(&tx)?;
+        nonce_pubkeys.push(nonce_keypair.pubkey());
+    }
+    Ok(nonce_pubkeys)
+}
+
+/// compute unit limit for given tx from simulating it: units consumed plus margin_percent.
+/// None if the simulation failed (e.g. given compute unit limit was too low)
+pub fn simulate_compute_unit_limit(
+    rpc_client: &RpcClient,
+    tx: &VersionedTransaction,
+    margin_percent: u32,
+) -> Option<u32> {
+    let res = rpc_client
+        .simulate_transaction_with_config(
+            tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc_client.commitment()),
+                ..Default::default()
+            },
+        )
+        .inspect_err(|err| log::debug!("Failed to simulate tx: {err}"))
+        .ok()?
+        .value;
+    if let Some(err) = res.err {
+        log::debug!("Simulated tx failed: {err}");
+        return None;
+    }
+    let units_consumed = res.units_consumed?;
+    u32::try_from(units_consumed * (100 + margin_percent as u64) / 100).ok()
+}
+
+/// non-signer accounts of given ixs in order of appearance, to be put into lookup tables.
+/// NOTE: invoked program ids are always kept as static keys when compiling
+pub fn get_lookup_table_addresses<'a>(ixs: impl Iterator<Item = &'a Instruction>) -> Vec<Pubkey> {
+    let mut seen = HashSet::new();
+    ixs.flat_map(|ix| ix.accounts.iter())
+        .filter(|meta| !meta.is_signer && seen.insert(meta.pubkey))
+        .map(|meta| meta.pubkey)
+        .collect()
+}
+
+// number of txs to create a lookup table holding given number of addresses (create ix is sent with the first extend ix)
+pub fn count_lookup_table_setup_txs(n_addresses: usize) -> usize {
+    n_addresses.div_ceil(LOOKUP_TABLE_EXTEND_CHUNK_SIZE)
+}
+
+pub fn get_lookup_table_rent(rpc_client: &RpcClient, n_addresses: usize) -> Result<u64> {
+    Ok(rpc_client
+        .get_minimum_balance_for_rent_exemption(LOOKUP_TABLE_META_SIZE + n_addresses * 32)?)
+}
+
+/// Creates lookup tables (authority: payer) holding given addresses, LOOKUP_TABLE_MAX_ADDRESSES each.
+/// Returns once every table is usable (i.e. the slot of the last extension has passed)
+pub fn create_lookup_tables(
+    rpc_client: &RpcClient,
+    payer: &dyn Signer,
+    addresses: &[Pubkey],
+) -> Result<Vec<AddressLookupTableAccount>> {
+    let mut lookup_table_accounts = Vec::new();
+    let mut recent_slot = 0;
+    for addresses in addresses.chunks(LOOKUP_TABLE_MAX_ADDRESSES) {
+        // NOTE: table address is derived from the recent slot, so every table needs a new one
+        recent_slot = loop {
+            let slot = rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+            if slot > recent_slot {
+                break slot;
+            }
+            std::thread::sleep(Duration::from_millis(LOOKUP_TABLE_POLL_INTERVAL_MILLIS));
+        };
+        let (create_ix, lookup_table_pubkey) =
+            create_lookup_table(payer.pubkey(), payer.pubkey(), recent_slot);
+        log::info!(
+            "Creating lookup table {lookup_table_pubkey} with {} addresses ...",
+            addresses.len()
+        );
+        for (i, chunk) in addresses.chunks(LOOKUP_TABLE_EXTEND_CHUNK_SIZE).enumerate() {
+            let extend_ix = extend_lookup_table(
+                lookup_table_pubkey,
+                payer.pubkey(),
+                Some(payer.pubkey()),
+                chunk.to_vec(),
+            );
+            let ixs = match i {
+                0 => vec![create_ix.clone(), extend_ix],
+                _ => vec![extend_ix],
+            };
+            let (tx, _) = prep_tx(rpc_client, payer, &ixs, &[])?;
+            rpc_client.send_and_confirm_transaction(&tx)?;
+        }
+        lookup_table_accounts.push(AddressLookupTableAccount {
+            key: lookup_table_pubkey,
+            addresses: addresses.to_vec(),
+        });
+    }
+
+    // NOTE: extended addresses can only be looked up after the slot they were added in
+    let last_extended_slot = rpc_client.get_slot()?;
+    while rpc_client.get_slot()? <= last_extended_slot {
+        std::thread::sleep(Duration::from_millis(LOOKUP_TABLE_POLL_INTERVAL_MILLIS));
+    }
+    Ok(lookup_table_accounts)
+}
+
+/// what sending the packed airdrop txs costs the payer
+#[derive(Debug)]
+pub struct SendCostEstimate {
+    pub n_txs: usize,
+    /// tokens leaving the source account, incl. grossed up transfer fees
+    pub total_amount_atomic: u64,
+    pub total_transfer_fee_atomic: u64,
+    pub signature_fees_lamports: u64,
+    pub priority_fees_lamports: u64,
+    pub ata_rent_lamports: u64,
+    /// rent and fees of creating lookup tables (rent is reclaimed by close-lookup-tables)
+    pub lookup_table_lamports: u64,
+    /// rent and fees of creating nonce accounts for the durable nonce pool
+    pub nonce_account_lamports: u64,
+}
+
+impl SendCostEstimate {
+    pub fn new(
+        n_txs: usize,
+        (total_amount_atomic, total_transfer_fee_atomic): (u64, u64),
+        compute_unit_limit: u32,
+        compute_unit_price: u64,
+        ata_rent_lamports: u64,
+        lookup_table_lamports: u64,
+        nonce_account_lamports: u64,
+    ) -> Self {
+        let priority_fee_per_tx =
+            (compute_unit_limit as u64 * compute_unit_price).div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+        Self {
+            n_txs,
+            total_amount_atomic,
+            total_transfer_fee_atomic,
+            signature_fees_lamports: n_txs as u64 * LAMPORTS_PER_SIGNATURE,
+            priority_fees_lamports: n_txs as u64 * priority_fee_per_tx,
+            ata_rent_lamports,
+            lookup_table_lamports,
+            nonce_account_lamports,
+        }
+    }
+
+    pub fn total_lamports(&self) -> u64 {
+        self.signature_fees_lamports
+            + self.priority_fees_lamports
+            + self.ata_rent_lamports
+            + self.lookup_table_lamports
+            + self.nonce_account_lamports
+    }
+}
+
 pub fn get_compute_budget_ixs(
     compute_unit_limit: u32,
     compute_unit_price: u64,
@@ -94,6 +1109,84 @@ pub fn get_compute_budget_ixs(
     ]
 }
 
+/// Compute unit price derived from recent prioritization fees of the accounts written by a tx
+#[derive(Debug, Clone, Copy)]
+pub struct PriorityFeePolicy {
+    /// percentile (0-100) of the sampled fees to pay
+    pub percentile: u8,
+    /// floor (micro lamports)
+    pub min_compute_unit_price: u64,
+    /// cap (micro lamports), also applied after escalation
+    pub max_compute_unit_price: u64,
+    /// increase over the price of the previous attempt for retried txs
+    pub escalation_percent: u32,
+}
+
+// nearest-rank (rounded down) percentile (0-100) of given sorted values, 0 if empty
+fn get_percentile(sorted_values: &[u64], percentile: u8) -> u64 {
+    match sorted_values.len() {
+        0 => 0,
+        n => sorted_values[(n - 1) * percentile.min(100) as usize / 100],
+    }
+}
+
+impl PriorityFeePolicy {
+    // NOTE: previous_compute_unit_price is the price of the last attempt (None for the first attempt).
+    //  shared_writable_accounts (e.g. payer and source) are sampled first, as they are written by every tx
+    pub fn compute_unit_price(
+        &self,
+        rpc_client: &RpcClient,
+        shared_writable_accounts: &[Pubkey],
+        ixs: &[Instruction],
+        previous_compute_unit_price: Option<u64>,
+    ) -> u64 {
+        let writable_accounts = get_writable_accounts(shared_writable_accounts, ixs);
+        let sampled_compute_unit_price = rpc_client
+            .get_recent_prioritization_fees(&writable_accounts)
+            .inspect_err(|err| log::warn!("Failed to get recent prioritization fees: {err}"))
+            .map(|fees| {
+                let mut fees: Vec<u64> = fees.iter().map(|fee| fee.prioritization_fee).collect();
+                fees.sort_unstable();
+                get_percentile(&fees, self.percentile)
+            })
+            .unwrap_or_default();
+        self.price_from_sample(sampled_compute_unit_price, previous_compute_unit_price)
+    }
+
+    // the sampled price, raised by the escalation over the previous attempt and bounded by floor and cap
+    fn price_from_sample(
+        &self,
+        sampled_compute_unit_price: u64,
+        previous_compute_unit_price: Option<u64>,
+    ) -> u64 {
+        let escalated_compute_unit_price = previous_compute_unit_price
+            .map(|price| price.saturating_mul(100 + self.escalation_percent as u64) / 100)
+            .unwrap_or_default();
+        sampled_compute_unit_price
+            .max(escalated_compute_unit_price)
+            .max(self.min_compute_unit_price)
+            .min(self.max_compute_unit_price)
+    }
+}
+
+// accounts written by given ixs, preceded by shared_writable_accounts, deduped in order of appearance
+// and truncated to the max number of accounts get_recent_prioritization_fees accepts
+fn get_writable_accounts(shared_writable_accounts: &[Pubkey], ixs: &[Instruction]) -> Vec<Pubkey> {
+    let mut seen = HashSet::new();
+    shared_writable_accounts
+        .iter()
+        .copied()
+        .chain(
+            ixs.iter()
+                .flat_map(|ix| ix.accounts.iter())
+                .filter(|account_meta| account_meta.is_writable)
+                .map(|account_meta| account_meta.pubkey),
+        )
+        .filter(|pubkey| seen.insert(*pubkey))
+        .take(PRIORITIZATION_FEE_MAX_ACCOUNTS)
+        .collect()
+}
+
 pub fn add_to_filename(path: &Path, name: &str) -> PathBuf {
     let mut res = path.to_path_buf();
     let stem = res.file_stem().unwrap().to_str().unwrap();
@@ -110,28 +1203,88 @@ pub fn create_backup_if_file_exists(path: &PathBuf) -> Result<()> {
         return Ok(());
     }
 
+    let backup_path = get_backup_path(path)?;
+    log::info!("Saving backup for {path:?} to {backup_path:?}");
+    std::fs::rename(path, backup_path)?;
+
+    Ok(())
+}
+
+// same as create_backup_if_file_exists, but keeps the file at given path (copies instead of moving it),
+// so that the path is never left without a file while it is being replaced
+pub fn copy_backup_if_file_exists(path: &PathBuf) -> Result<()> {
+    if !path.try_exists()? {
+        return Ok(());
+    }
+
+    let backup_path = get_backup_path(path)?;
+    log::info!("Saving backup for {path:?} to {backup_path:?}");
+    std::fs::copy(path, backup_path)?;
+
+    Ok(())
+}
+
+fn get_backup_path(path: &Path) -> Result<PathBuf> {
     let mut n = 0;
-    let backup_path = loop {
+    loop {
         let target = add_to_filename(path, &n.to_string());
         if !target.try_exists()? {
-            break target;
+            return Ok(target);
         }
         n += 1;
-    };
+    }
+}
 
-    log::info!("Saving backup for {path:?} to {backup_path:?}");
-    std::fs::rename(path, backup_path)?;
+/// Writes given data to a temporary file next to path, syncs it to disk and then renames it over
+/// path, so that a crash mid-write leaves either the old or the new file, never a truncated one
+pub fn write_file_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp_path = add_to_filename(path, "tmp");
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+    // NOTE: also syncs the directory so that the rename itself is durable
+    //  (directories cannot be opened for syncing on windows)
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
+// on SIGINT/SIGTERM (e.g. Ctrl-C), sets the interrupted flag for long running loops to stop at the next
+// safe point, so that the current state can still be saved. a second signal exits immediately
+pub fn set_interrupt_handler() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            log::error!("Interrupted again, exiting without saving");
+            std::process::exit(130);
+        }
+        log::warn!(
+            "Interrupted, stopping after in-flight work and saving (interrupt again to force exit)"
+        );
+    })?;
     Ok(())
 }
 
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
 // prompt for confirmation for a potentially mistakable action
-pub fn prompt_confirmation(msg: &str) -> bool {
+// NOTE: requires typing one of the expected values (e.g. total amount) instead of Y/N to avoid proceeding by habit
+pub fn prompt_confirmation(msg: &str, expected: &[&str]) -> bool {
     let mut buffer = String::new();
-    print!("{msg} (Y/N): ");
+    print!("{msg} (type {} to proceed): ", expected.join(" or "));
     std::io::stdout().flush().unwrap();
     std::io::stdin().read_line(&mut buffer).unwrap();
-    return buffer.trim().to_uppercase().as_str() == "Y";
+    expected.contains(&buffer.trim())
 }
 
 // Sat Jun  8 05:57:44 AM UTC 2024
@@ -149,3 +1302,287 @@ pub fn confirm_signature(rpc_client: &RpcClient, sig: &Signature) -> Result<Opti
         .as_ref()
         .map(|tx_status| tx_status.status.is_ok()))
 }
+
+/// Waits until given slot is finalized, then returns the (slot, blockhash) of the first block at or after it
+/// (given slot might have been skipped)
+pub fn get_blockhash_at_or_after_slot(rpc_client: &RpcClient, slot: Slot) -> Result<(Slot, Hash)> {
+    loop {
+        let current_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+        if current_slot >= slot {
+            break;
+        }
+        log::info!("Waiting for slot {slot} to be finalized (current: {current_slot}) ...");
+        std::thread::sleep(Duration::from_secs(RAFFLE_SLOT_POLL_INTERVAL_SECS));
+    }
+    let block_slot = *rpc_client
+        .get_blocks_with_limit_and_commitment(slot, 1, CommitmentConfig::finalized())?
+        .first()
+        .ok_or(Error::BlockNotFound(slot))?;
+    let block = rpc_client.get_block_with_config(
+        block_slot,
+        RpcBlockConfig {
+            encoding: None,
+            transaction_details: Some(TransactionDetails::None),
+            rewards: Some(false),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    Ok((block_slot, Hash::from_str(&block.blockhash)?))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn from_hex(s: &str) -> Result<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    // NOTE: from_str_radix alone would also accept a leading sign (e.g. "+1")
+    if s.is_empty() || s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::HexError(s.to_string()));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| Error::HexError(s.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::instruction::AccountMeta;
+    use spl_token_2022::instruction::transfer_checked;
+
+    use super::*;
+
+    fn transfer_ix(data_len: usize) -> Instruction {
+        let mut ix = transfer_checked(
+            &spl_token_2022::id(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &[],
+            1,
+            0,
+        )
+        .unwrap();
+        ix.data.resize(ix.data.len().max(data_len), 0);
+        ix
+    }
+
+    #[test]
+    fn from_hex_round_trips_to_hex() {
+        let bytes = vec![0x00, 0x01, 0xab, 0xff];
+        assert_eq!(to_hex(&bytes), "0001abff");
+        assert_eq!(from_hex("0001abff").unwrap(), bytes);
+        assert_eq!(from_hex("0x0001ABFF").unwrap(), bytes);
+    }
+
+    #[test]
+    fn from_hex_rejects_invalid_input() {
+        assert!(matches!(from_hex("abc"), Err(Error::HexError(_))));
+        assert!(matches!(from_hex("zz"), Err(Error::HexError(_))));
+        assert!(matches!(from_hex("+1"), Err(Error::HexError(_))));
+        assert!(matches!(from_hex(""), Err(Error::HexError(_))));
+        assert!(matches!(from_hex("0x"), Err(Error::HexError(_))));
+        assert!(matches!(from_hex("0x0x01"), Err(Error::HexError(_))));
+    }
+
+    #[test]
+    fn get_tlv_entries_reads_extensions_unknown_to_spl_token_2022() {
+        let mut paused_config = vec![0; PAUSABLE_CONFIG_PAUSED_OFFSET + 1];
+        paused_config[PAUSABLE_CONFIG_PAUSED_OFFSET] = 1;
+        let mut tlv_data = Vec::new();
+        for (extension_type, value) in [
+            (ExtensionType::MemoTransfer as u16, vec![1]),
+            (PAUSABLE_EXTENSION_TYPE, paused_config.clone()),
+            (999, vec![]),
+        ] {
+            tlv_data.extend_from_slice(&extension_type.to_le_bytes());
+            tlv_data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            tlv_data.extend_from_slice(&value);
+        }
+        // NOTE: an uninitialized type (e.g. zeroed space left for a realloc) ends the entries
+        tlv_data.extend_from_slice(&[0; 8]);
+
+        let entries = get_tlv_entries(&tlv_data).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (ExtensionType::MemoTransfer as u16, [1].as_slice()),
+                (PAUSABLE_EXTENSION_TYPE, paused_config.as_slice()),
+                (999, [].as_slice()),
+            ]
+        );
+        let names: Vec<String> = entries
+            .iter()
+            .map(|(extension_type, _)| get_extension_name(*extension_type))
+            .collect();
+        assert_eq!(names, ["MemoTransfer", "Pausable", "Unknown(999)"]);
+
+        // value longer than the data
+        assert!(get_tlv_entries(&[1, 0, 8, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn get_percentile_indexes_sorted_values() {
+        let values: Vec<u64> = (1..=100).collect();
+        assert_eq!(get_percentile(&values, 0), 1);
+        assert_eq!(get_percentile(&values, 50), 50);
+        assert_eq!(get_percentile(&values, 75), 75);
+        assert_eq!(get_percentile(&values, 100), 100);
+        // clamped to 100
+        assert_eq!(get_percentile(&values, 255), 100);
+        assert_eq!(get_percentile(&[7], 75), 7);
+        assert_eq!(get_percentile(&[], 75), 0);
+    }
+
+    #[test]
+    fn estimate_compute_units_by_ix_kind() {
+        let payer = Pubkey::new_unique();
+        let create_ata_ix =
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &payer,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &spl_token_2022::id(),
+            );
+        assert_eq!(
+            estimate_compute_units(&create_ata_ix),
+            CREATE_ATA_CU_ESTIMATE
+        );
+        let memo_ix = spl_memo::build_memo(b"airdrop", &[]);
+        assert_eq!(estimate_compute_units(&memo_ix), MEMO_CU_ESTIMATE);
+        assert_eq!(
+            estimate_compute_units(&transfer_ix(0)),
+            TRANSFER_CU_ESTIMATE
+        );
+    }
+
+    #[test]
+    fn estimate_compute_units_counts_extra_accounts_as_transfer_hook() {
+        let mut hook_transfer_ix = transfer_ix(0);
+        // extra account metas, then the hook program and its validation account
+        for _ in 0..3 {
+            hook_transfer_ix
+                .accounts
+                .push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+        }
+        assert_eq!(
+            estimate_compute_units(&hook_transfer_ix),
+            TRANSFER_CU_ESTIMATE + TRANSFER_HOOK_CU_ESTIMATE
+        );
+        // a single extra account (e.g. a hook without extra account metas) is enough
+        let mut hook_transfer_ix = transfer_ix(0);
+        hook_transfer_ix
+            .accounts
+            .push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+        assert_eq!(
+            estimate_compute_units(&hook_transfer_ix),
+            TRANSFER_CU_ESTIMATE + TRANSFER_HOOK_CU_ESTIMATE
+        );
+    }
+
+    #[test]
+    fn price_from_sample_applies_floor_cap_and_escalation() {
+        let policy = PriorityFeePolicy {
+            percentile: 75,
+            min_compute_unit_price: 1_000,
+            max_compute_unit_price: 100_000,
+            escalation_percent: 50,
+        };
+        assert_eq!(policy.price_from_sample(5_000, None), 5_000);
+        assert_eq!(policy.price_from_sample(0, None), 1_000);
+        assert_eq!(policy.price_from_sample(1_000_000, None), 100_000);
+        // escalated over the previous attempt unless the sample is higher
+        assert_eq!(policy.price_from_sample(5_000, Some(8_000)), 12_000);
+        assert_eq!(policy.price_from_sample(20_000, Some(8_000)), 20_000);
+        // the cap also applies after escalation
+        assert_eq!(policy.price_from_sample(0, Some(90_000)), 100_000);
+        assert_eq!(policy.price_from_sample(0, Some(u64::MAX)), 100_000);
+    }
+
+    #[test]
+    fn get_writable_accounts_puts_shared_accounts_first() {
+        let payer = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let ixs: Vec<Instruction> = (0..PRIORITIZATION_FEE_MAX_ACCOUNTS)
+            .map(|_| {
+                Instruction::new_with_bytes(
+                    spl_token_2022::id(),
+                    &[],
+                    vec![
+                        AccountMeta::new(source, false),
+                        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                        AccountMeta::new(Pubkey::new_unique(), false),
+                        AccountMeta::new(payer, true),
+                    ],
+                )
+            })
+            .collect();
+        let writable_accounts = get_writable_accounts(&[payer, source], &ixs);
+        assert_eq!(writable_accounts.len(), PRIORITIZATION_FEE_MAX_ACCOUNTS);
+        assert_eq!(writable_accounts[..2], [payer, source]);
+        assert_eq!(writable_accounts[2], ixs[0].accounts[2].pubkey);
+        assert_eq!(
+            writable_accounts.iter().collect::<HashSet<_>>().len(),
+            PRIORITIZATION_FEE_MAX_ACCOUNTS
+        );
+    }
+
+    #[test]
+    fn pack_ixs_respects_compute_unit_limit() {
+        let payer = Pubkey::new_unique();
+        let items: Vec<(usize, Vec<Instruction>)> =
+            (0..5).map(|idx| (idx, vec![transfer_ix(0)])).collect();
+        let chunks = pack_ixs(&payer, items, &[], 2 * TRANSFER_CU_ESTIMATE, false);
+        let idxs: Vec<Vec<usize>> = chunks
+            .iter()
+            .map(|chunk| chunk.iter().map(|(idx, _)| *idx).collect())
+            .collect();
+        assert_eq!(idxs, vec![vec![0, 1], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn pack_ixs_respects_packet_size() {
+        let payer = Pubkey::new_unique();
+        let items: Vec<(usize, Vec<Instruction>)> =
+            (0..20).map(|idx| (idx, vec![transfer_ix(0)])).collect();
+        for with_advance_nonce in [false, true] {
+            let chunks = pack_ixs(&payer, items.clone(), &[], u32::MAX, with_advance_nonce);
+            assert!(chunks.len() > 1);
+            // every item is packed exactly once, in order
+            let idxs: Vec<usize> = chunks.iter().flatten().map(|(idx, _)| *idx).collect();
+            assert_eq!(idxs, (0..20).collect::<Vec<_>>());
+            for chunk in chunks {
+                let ixs: Vec<Instruction> = with_advance_nonce
+                    .then(|| advance_nonce_account(&Pubkey::new_unique(), &payer))
+                    .into_iter()
+                    .chain(get_compute_budget_ixs(u32::MAX, 0))
+                    .chain(chunk.into_iter().flat_map(|(_, ixs)| ixs))
+                    .collect();
+                assert!(serialized_tx_size(&payer, &ixs, &[]).unwrap() <= PACKET_DATA_SIZE);
+            }
+        }
+    }
+
+    #[test]
+    fn pack_ixs_gives_oversized_item_its_own_chunk() {
+        let payer = Pubkey::new_unique();
+        let items = vec![
+            (0, vec![transfer_ix(0)]),
+            (1, vec![transfer_ix(PACKET_DATA_SIZE)]),
+            (2, vec![transfer_ix(0)]),
+        ];
+        let chunks = pack_ixs(&payer, items, &[], u32::MAX, false);
+        let idxs: Vec<Vec<usize>> = chunks
+            .iter()
+            .map(|chunk| chunk.iter().map(|(idx, _)| *idx).collect())
+            .collect();
+        assert_eq!(idxs, vec![vec![0], vec![1], vec![2]]);
+    }
+}